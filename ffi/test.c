@@ -0,0 +1,58 @@
+/* C APIの動作確認。make -C ffi test で動かす */
+#include <stdio.h>
+#include <string.h>
+#include "wordle_solver.h"
+
+static int failures = 0;
+
+#define CHECK(expr)                                                   \
+    do {                                                              \
+        if (!(expr)) {                                                \
+            fprintf(stderr, "%s:%d: %s\n", __FILE__, __LINE__, #expr); \
+            failures++;                                               \
+        }                                                             \
+    } while (0)
+
+int main(void) {
+    WordleBoard *board = NULL;
+    size_t remaining = 0;
+    char next[32];
+
+    /* 埋め込みの英語の単語リスト */
+    CHECK(wordle_board_new("position_freq", NULL, NULL, 0, NULL, 0, &board) == WORDLE_ERROR_OK);
+    CHECK(wordle_board_remaining(board, &remaining) == WORDLE_ERROR_OK);
+    CHECK(remaining == 2315);
+    CHECK(wordle_board_filter(board, "soare", "__yy_") == WORDLE_ERROR_OK);
+    CHECK(wordle_board_remaining(board, &remaining) == WORDLE_ERROR_OK);
+    CHECK(remaining == 42);
+    CHECK(wordle_board_next(board, next, sizeof next) == WORDLE_ERROR_OK);
+    CHECK(strlen(next) == 5);
+    CHECK(wordle_board_filter(board, "cigar", "_____") == WORDLE_ERROR_CONFLICT);
+    CHECK(wordle_board_filter(board, "cigar", "gg") == WORDLE_ERROR_INVALID_STATUS);
+    CHECK(wordle_board_filter(board, "cig", "ggggg") == WORDLE_ERROR_INVALID_WORD);
+    CHECK(wordle_board_next(board, next, 3) == WORDLE_ERROR_BUFFER_TOO_SMALL);
+    CHECK(wordle_board_reset(board) == WORDLE_ERROR_OK);
+    CHECK(wordle_board_remaining(board, &remaining) == WORDLE_ERROR_OK);
+    CHECK(remaining == 2315);
+    wordle_board_free(board);
+
+    /* 単語リストを渡す */
+    const char *answers = "cigar\nrebut\nsissy\n";
+    const char *guesses = "soare\n";
+    board = NULL;
+    CHECK(wordle_board_new("avg_info_max", "english", (const uint8_t *)answers, strlen(answers),
+                           (const uint8_t *)guesses, strlen(guesses), &board) == WORDLE_ERROR_OK);
+    CHECK(wordle_board_filter(board, "soare", "__yy_") == WORDLE_ERROR_OK);
+    CHECK(wordle_board_next(board, next, sizeof next) == WORDLE_ERROR_OK);
+    CHECK(strcmp(next, "cigar") == 0);
+    wordle_board_free(board);
+
+    CHECK(wordle_board_new("nothing", NULL, NULL, 0, NULL, 0, &board) == WORDLE_ERROR_UNKNOWN_TACTIC);
+    CHECK(wordle_board_filter(NULL, "soare", "_____") == WORDLE_ERROR_NULL_POINTER);
+    CHECK(strcmp(wordle_error_message(WORDLE_ERROR_CONFLICT), "the row conflicts with earlier rows") == 0);
+
+    if (failures == 0) {
+        printf("ok\n");
+    }
+    return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,103 @@
+/* generated by cbindgen from src/ffi.rs. do not edit. */
+
+#ifndef WORDLE_SOLVER_H
+#define WORDLE_SOLVER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum WordleError {
+  WORDLE_ERROR_OK = 0,
+  WORDLE_ERROR_NULL_POINTER = 1,
+  WORDLE_ERROR_INVALID_UTF8 = 2,
+  WORDLE_ERROR_UNKNOWN_TACTIC = 3,
+  WORDLE_ERROR_UNKNOWN_LANGUAGE = 4,
+  WORDLE_ERROR_INVALID_WORD_LIST = 5,
+  WORDLE_ERROR_INVALID_WORD = 6,
+  WORDLE_ERROR_INVALID_STATUS = 7,
+  WORDLE_ERROR_UNPRODUCIBLE = 8,
+  WORDLE_ERROR_CONFLICT = 9,
+  WORDLE_ERROR_NO_CANDIDATES = 10,
+  WORDLE_ERROR_BUFFER_TOO_SMALL = 11,
+  WORDLE_ERROR_PANIC = 12,
+} WordleError;
+
+typedef struct WordleBoard WordleBoard;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * boardを作って`out`に入れる。`tactic`は"avg_info_max"、"position_freq"、"win_prob"のどれか。
+ * `answers`と`guesses`は一行一単語のUTF-8。`answers`がNULLなら埋め込みの英語の単語リストを使う。
+ * `language_name`は"english"、"kana"、"german"、"spanish"のどれか（NULLなら"english"）。
+ *
+ * # Safety
+ * 文字列はNUL終端、バッファはその長さだけ読めること。作ったboardは`wordle_board_free`で解放する。
+ */
+enum WordleError wordle_board_new(const char *tactic,
+                                  const char *language_name,
+                                  const uint8_t *answers,
+                                  size_t answers_len,
+                                  const uint8_t *guesses,
+                                  size_t guesses_len,
+                                  struct WordleBoard **out);
+
+/**
+ * `wordle_board_new`で作ったboardを解放する。NULLなら何もしない。
+ *
+ * # Safety
+ * 解放したboardはもう使わないこと。
+ */
+void wordle_board_free(struct WordleBoard *board);
+
+/**
+ * 入力した単語とstatus（"__yy_"や絵文字）で絞り込む。これまでの行と矛盾するときはboardを変えない。
+ *
+ * # Safety
+ * `board`は生きているboard、文字列はNUL終端であること。
+ */
+enum WordleError wordle_board_filter(struct WordleBoard *board,
+                                     const char *word,
+                                     const char *status);
+
+/**
+ * 次の単語をNUL終端のUTF-8で`buffer`に書く。
+ *
+ * # Safety
+ * `board`は生きているboard、`buffer`は`buffer_len`バイト書けること。
+ */
+enum WordleError wordle_board_next(const struct WordleBoard *board,
+                                   char *buffer,
+                                   size_t buffer_len);
+
+/**
+ * 残りの候補の数を`out`に書く。
+ *
+ * # Safety
+ * `board`は生きているboard、`out`は書けること。
+ */
+enum WordleError wordle_board_remaining(const struct WordleBoard *board, size_t *out);
+
+/**
+ * 絞り込む前に戻す。
+ *
+ * # Safety
+ * `board`は生きているboardであること。
+ */
+enum WordleError wordle_board_reset(struct WordleBoard *board);
+
+/**
+ * エラーコードの説明。静的なNUL終端の文字列を返す。
+ */
+const char *wordle_error_message(enum WordleError error);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* WORDLE_SOLVER_H */
@@ -1,3 +1,46 @@
+use nkowne63_wordle_solver_rs_01::{
+    repls::leko_competition,
+    tactics::{avg_info_max, position_freq},
+    tree::DecisionTree,
+    CANDITATES,
+};
+use std::{path::Path, sync::Arc, time::Duration};
+
+// wordle-solver [--protocol json] [--tactic <position_freq|avg_info_max>] [--time-limit <ms>]
+//               [--tree <file>]
 fn main() {
-    nkowne63_wordle_solver_rs_01::repls::leko_competition::position_freq();
+    let args: Vec<String> = std::env::args().collect();
+    let option = |name: &str| {
+        args.windows(2)
+            .find(|pair| pair[0] == name)
+            .map(|pair| pair[1].as_str())
+    };
+    let json = option("--protocol") == Some("json");
+    let time_limit = match option("--time-limit").map(|ms| ms.parse::<u64>()) {
+        Some(Ok(ms)) => Some(Duration::from_millis(ms)),
+        Some(Err(e)) => return eprintln!("--time-limit: {}", e),
+        None => None,
+    };
+    // 木にない局面では--tacticで計算する
+    let tree = match option("--tree").map(|path| DecisionTree::load(&*CANDITATES, Path::new(path)))
+    {
+        Some(Ok(tree)) => Some(Arc::new(tree)),
+        Some(Err(message)) => return eprintln!("{}", message),
+        None => None,
+    };
+    match (option("--tactic"), json) {
+        (None | Some("position_freq"), true) => {
+            leko_competition::json_lines::<position_freq::Board>(time_limit, tree)
+        }
+        (None | Some("position_freq"), false) => {
+            leko_competition::leko::<position_freq::Board>(time_limit, tree)
+        }
+        (Some("avg_info_max"), true) => {
+            leko_competition::json_lines::<avg_info_max::Board>(time_limit, tree)
+        }
+        (Some("avg_info_max"), false) => {
+            leko_competition::leko::<avg_info_max::Board>(time_limit, tree)
+        }
+        (Some(tactic), _) => eprintln!("unknown tactic: {}", tactic),
+    }
 }
@@ -1,4 +1,219 @@
+#[cfg(feature = "local")]
+use nkowne63_wordle_solver_rs_01::{
+    game::Game,
+    priors::{Priors, Weighted},
+    repls::local::{self, Command},
+    server::{serve, Service},
+    traps::MIN_CLUSTER,
+    tree::Format,
+    variants::{language, LoadedVariant, Variant},
+    CANDITATES,
+};
+#[cfg(feature = "local")]
+use std::{net::TcpListener, path::Path, sync::Arc, time::SystemTime};
+
+// local-solver [--tactic <position_freq|avg_info_max|win_prob>] [--endgame] [--max-guesses <n>]
+//              [--variant <kana|german|spanish> --answers <file> --guesses <file>]
+//              [--priors <file> [--all-answers]]
+// local-solver play [--seed <n> | --index <n>] [--hard] [--max-guesses <n>] [--variant ...]
+// local-solver coach <answer> <guess>... [--tactic ...] [--endgame] [--variant ...]
+// local-solver script [<file>] [--json] [--tactic ...] [--endgame] [--variant ...]
+// local-solver traps [--first <word>] [--min-size <n>] [--max-guesses <n>] [--tactic ...]
+//                    [--endgame] [--variant ...]
+// local-solver tree [--first <word>] [--format <json|text|dot>] [--max-guesses <n>]
+//                   [--tactic ...] [--endgame] [--variant ...]
+// local-solver verify <file> [--variant ...]
+// local-solver tournament [--tactics <tactic>,...] [--first <word>] [--max-guesses <n>] [--json]
+//                         [--variant ...]
+// local-solver serve [--port <n>] [--max-guesses <n>] [--variant ...]
+// local-solver reverse [--answer <word>] [--variant ...] < share.txt
+#[cfg(feature = "local")]
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+}
+
+#[cfg(feature = "local")]
+fn variant_from_args(args: &[String]) -> Result<Box<dyn Variant>, String> {
+    let variant: Box<dyn Variant> = match option(args, "--variant") {
+        None => Box::new(&*CANDITATES),
+        Some(name) => {
+            let language = language(name).ok_or(format!("unknown variant: {}", name))?;
+            let answers = option(args, "--answers").ok_or("--answers <file> is required")?;
+            let guesses = option(args, "--guesses").ok_or("--guesses <file> is required")?;
+            let variant = LoadedVariant::load(language, Path::new(answers), Path::new(guesses))?;
+            Box::new(variant)
+        }
+    };
+    match option(args, "--priors") {
+        None => Ok(variant),
+        Some(path) => {
+            let priors = Priors::load(variant.as_ref(), Path::new(path))?;
+            Ok(Box::new(Weighted {
+                variant,
+                priors,
+                all_answers: args.iter().any(|arg| arg == "--all-answers"),
+            }))
+        }
+    }
+}
+
+#[cfg(feature = "local")]
+fn game_from_args(args: &[String], variant: &dyn Variant) -> Result<Game, String> {
+    let number = |name: &str| -> Result<Option<u64>, String> {
+        option(args, name)
+            .map(|n| n.parse().map_err(|_| format!("{} must be a number", name)))
+            .transpose()
+    };
+    let game = match (number("--index")?, number("--seed")?) {
+        (Some(index), _) => {
+            Game::from_variant(variant, index as usize).ok_or("--index is out of range")?
+        }
+        (None, Some(seed)) => Game::random_from_variant(variant, seed)?,
+        (None, None) => {
+            let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
+            Game::random_from_variant(variant, now.map(|d| d.as_secs()).unwrap_or(0))?
+        }
+    };
+    let max_guesses = number("--max-guesses")?.unwrap_or(6) as usize;
+    Ok(game
+        .with_max_guesses(max_guesses)
+        .with_hard_mode(args.iter().any(|arg| arg == "--hard")))
+}
+
 fn main() {
     #[cfg(feature = "local")]
-    nkowne63_wordle_solver_rs_01::repls::local::position_freq();
+    {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let variant = match variant_from_args(&args) {
+            Ok(variant) => variant,
+            Err(message) => return eprintln!("{}", message),
+        };
+        // --tacticで選んだ戦略でcommandを動かす。defaultは--tacticがないときの戦略
+        let tactic = option(&args, "--tactic").map(|s| s.as_str());
+        let endgame = args.iter().any(|arg| arg == "--endgame");
+        let run = |variant, default: &str, command| {
+            if let Err(message) = local::run(variant, tactic.unwrap_or(default), endgame, command) {
+                eprintln!("{}", message);
+            }
+        };
+        // 手数の上限。playはgame_from_argsで同じ引数を読む
+        let max_guesses = match option(&args, "--max-guesses").map(|arg| arg.parse::<usize>()) {
+            Some(Ok(max_guesses)) => max_guesses,
+            Some(Err(e)) => return eprintln!("--max-guesses: {}", e),
+            None => 6,
+        };
+        if args.first().map(|s| s.as_str()) == Some("play") {
+            return match game_from_args(&args, variant.as_ref()) {
+                Ok(game) => local::play(variant, game),
+                Err(message) => eprintln!("{}", message),
+            };
+        }
+        if args.first().map(|s| s.as_str()) == Some("coach") {
+            let words: Result<Vec<_>, String> = args[1..]
+                .iter()
+                .take_while(|arg| !arg.starts_with("--"))
+                .map(|arg| variant.parse_word(arg))
+                .collect();
+            let (answer, guesses) = match words {
+                Ok(words) if !words.is_empty() => (words[0], words[1..].to_vec()),
+                Ok(_) => return eprintln!("usage: coach <answer> <guess>..."),
+                Err(message) => return eprintln!("{}", message),
+            };
+            return run(variant, "avg_info_max", Command::Coach { answer, guesses });
+        }
+        if args.first().map(|s| s.as_str()) == Some("script") {
+            let path = args
+                .get(1)
+                .filter(|arg| !arg.starts_with("--"))
+                .map(Path::new);
+            let json = args.iter().any(|arg| arg == "--json");
+            return run(variant, "position_freq", Command::Script { path, json });
+        }
+        if args.first().map(|s| s.as_str()) == Some("traps") {
+            let first = match option(&args, "--first").map(|arg| variant.parse_word(arg)) {
+                Some(Ok(word)) => Some(word),
+                Some(Err(message)) => return eprintln!("{}", message),
+                None => None,
+            };
+            let min_size = match option(&args, "--min-size").map(|arg| arg.parse::<usize>()) {
+                Some(Ok(size)) => size,
+                Some(Err(e)) => return eprintln!("--min-size: {}", e),
+                None => MIN_CLUSTER,
+            };
+            return run(
+                variant,
+                "position_freq",
+                Command::Traps {
+                    first,
+                    min_size,
+                    max_guesses,
+                },
+            );
+        }
+        if args.first().map(|s| s.as_str()) == Some("tree") {
+            let first = match option(&args, "--first").map(|arg| variant.parse_word(arg)) {
+                Some(Ok(word)) => Some(word),
+                Some(Err(message)) => return eprintln!("{}", message),
+                None => None,
+            };
+            let format = match option(&args, "--format").map(|arg| arg.parse::<Format>()) {
+                Some(Ok(format)) => format,
+                Some(Err(message)) => return eprintln!("{}", message),
+                None => Format::Text,
+            };
+            return run(
+                variant,
+                "position_freq",
+                Command::Tree {
+                    first,
+                    format,
+                    max_guesses,
+                },
+            );
+        }
+        if args.first().map(|s| s.as_str()) == Some("tournament") {
+            let first = match option(&args, "--first").map(|arg| variant.parse_word(arg)) {
+                Some(Ok(word)) => Some(word),
+                Some(Err(message)) => return eprintln!("{}", message),
+                None => None,
+            };
+            let tactics: Vec<&str> = option(&args, "--tactics")
+                .map_or("position_freq,avg_info_max", |s| s.as_str())
+                .split(',')
+                .collect();
+            let json = args.iter().any(|arg| arg == "--json");
+            return local::tournament(variant, &tactics, first, max_guesses, json);
+        }
+        if args.first().map(|s| s.as_str()) == Some("verify") {
+            return match args.get(1).filter(|arg| !arg.starts_with("--")) {
+                Some(path) => local::verify(variant, Path::new(path)),
+                None => eprintln!("usage: verify <file>"),
+            };
+        }
+        if args.first().map(|s| s.as_str()) == Some("serve") {
+            let port = option(&args, "--port").map_or("8080", |s| s.as_str());
+            let listener = match TcpListener::bind(format!("127.0.0.1:{}", port)) {
+                Ok(listener) => listener,
+                Err(e) => return eprintln!("{}", e),
+            };
+            let service = Arc::new(Service::new(variant).with_max_guesses(max_guesses));
+            println!("listening on http://127.0.0.1:{}", port);
+            if let Err(e) = serve(service, listener) {
+                eprintln!("{}", e);
+            }
+            return;
+        }
+        if args.first().map(|s| s.as_str()) == Some("reverse") {
+            let answer = option(&args, "--answer")
+                .map(|arg| variant.parse_word(arg))
+                .transpose();
+            return match answer {
+                Ok(answer) => local::reverse(variant, answer),
+                Err(message) => eprintln!("{}", message),
+            };
+        }
+        run(variant, "position_freq", Command::Repl { max_guesses });
+    }
 }
@@ -0,0 +1,110 @@
+use crate::{
+    enums::{Status, Word},
+    tactics::{avg_info_max::expected_info, solver::Solver},
+};
+use std::fmt;
+
+// 一手ごとの評価
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnReport {
+    pub turn: usize,
+    pub guess: Word,
+    pub status: Status,
+    pub before: usize,
+    pub after: usize,
+    // 入力した単語で得られるはずだった平均情報量
+    pub expected_info: f64,
+    pub best_guess: Word,
+    pub best_info: f64,
+    // 実際に得られた情報量と、その期待値との差（運）
+    pub actual_info: f64,
+    pub luck: f64,
+    // solverの一手と比べて0から100
+    pub skill: f64,
+}
+
+impl fmt::Display for TurnReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} | {} -> {} | info {:.2} (best {} {:.2}) | actual {:.2} luck {:+.2} | skill {:.0}",
+            self.turn,
+            self.guess,
+            self.status,
+            self.before,
+            self.after,
+            self.expected_info,
+            self.best_guess,
+            self.best_info,
+            self.actual_info,
+            self.luck,
+            self.skill
+        )
+    }
+}
+
+// 終わったゲームをsolverでなぞって、一手ずつ評価する
+pub fn analyze<S: Solver>(mut solver: S, guesses: &[Word], answer: &Word) -> Vec<TurnReport> {
+    let mut reports = vec![];
+    for (index, guess) in guesses.iter().enumerate() {
+        let remaining = solver.remaining().to_vec();
+        if remaining.is_empty() {
+            break;
+        }
+        let status = Word::to_status(guess, answer);
+        let best_guess = solver.next();
+        let expected = expected_info(guess, &remaining, None);
+        let best_info = expected_info(&best_guess, &remaining, None);
+        solver.filter(guess, &status);
+        let after = solver.remaining().len();
+        let actual_info = (remaining.len() as f64 / after.max(1) as f64).log2();
+        let skill = if best_info > 0.0 {
+            (expected / best_info).min(1.0) * 100.0
+        } else if expected > 0.0 || remaining.contains(guess) {
+            100.0
+        } else {
+            0.0
+        };
+        reports.push(TurnReport {
+            turn: index + 1,
+            guess: *guess,
+            status,
+            before: remaining.len(),
+            after,
+            expected_info: expected,
+            best_guess,
+            best_info,
+            actual_info,
+            luck: actual_info - expected,
+            skill,
+        });
+        if guess == answer {
+            break;
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tactics::avg_info_max::Board;
+    #[test]
+    fn grade() {
+        let words: Vec<Word> = ["abcde", "fghij", "klmno", "pqrst", "afkpz"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let board = Board::new(words[..4].to_vec(), words.clone());
+        let guesses = vec![words[1], words[2]];
+        let reports = analyze(board, &guesses, &words[2]);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].before, 4);
+        assert_eq!(reports[0].after, 3);
+        assert_eq!(reports[0].best_guess, words[4]);
+        assert!((reports[0].best_info - 2.0).abs() < 1e-9);
+        assert!(reports[0].skill < 100.0);
+        assert_eq!(reports[1].after, 1);
+        assert!(reports[1].luck > 0.0);
+    }
+}
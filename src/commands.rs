@@ -1,24 +1,176 @@
 use crate::{
     enums::{Status, Word},
+    knowledge::Knowledge,
+    patterns::Partition,
+    priors::Priors,
+    session::{fingerprint, Session},
     tactics::solver::Solver,
-    CANDITATES,
+    traps::{detect, MIN_CLUSTER},
+    validation::{check_filter, FeedbackError},
+    variants::Variant,
 };
 use repl_rs::{Command, Convert, Error as ReplError, Parameter, Repl, Value};
-use std::collections::HashMap;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{BufRead, Write},
+    mem,
+    path::Path,
+};
 
 pub struct ReplContext<T: Solver> {
     board: T,
+    variant: Box<dyn Variant>,
+    dictionary: Vec<Word>,
+    history: Vec<(Word, Status)>,
+    hard_mode: bool,
+    max_guesses: usize,
+}
+
+impl<T: ReplFunctions> ReplContext<T> {
+    pub fn new(variant: Box<dyn Variant>) -> Self {
+        ReplContext {
+            board: T::reset(variant.as_ref()),
+            dictionary: variant.get_all_words(),
+            variant,
+            history: vec![],
+            hard_mode: false,
+            max_guesses: 6,
+        }
+    }
+    pub fn with_max_guesses(mut self, max_guesses: usize) -> Self {
+        self.max_guesses = max_guesses;
+        self
+    }
+    // 入力された行を確かめてから反映する。filterもloadもスクリプトもここを通す
+    fn apply(&mut self, word: Word, status: Status) -> Result<(), CommandError> {
+        if self.hard_mode {
+            Knowledge::from_history(&self.history)
+                .check_hard_mode(&word)
+                .map_err(|message| CommandError::Parse(format!("hard mode: {}", message)))?;
+        }
+        <T as ReplFunctions>::filter(
+            word,
+            status,
+            &mut self.board,
+            &self.history,
+            &self.dictionary,
+        )?;
+        self.history.push((word, status));
+        Ok(())
+    }
+    fn suggest(&mut self) -> Result<String, CommandError> {
+        match <T as ReplFunctions>::next(&mut self.board) {
+            Some(word) => Ok(self.variant.display_word(&word)),
+            None => Err(CommandError::Parse("no canditates remain".to_string())),
+        }
+    }
+    fn set_hard_mode(&mut self, mode: &str) -> Result<(), CommandError> {
+        self.hard_mode = match mode {
+            "on" => true,
+            "off" => false,
+            _ => return Err(CommandError::Parse("mode must be on or off".to_string())),
+        };
+        Ok(())
+    }
+    // 保存された行をはじめから入力しなおす。失敗したら元の状態に戻す。
+    // 戦略が違っても行から絞り込みなおせるので、警告を返して読み込む
+    fn restore(&mut self, session: &Session) -> Result<Option<String>, CommandError> {
+        session
+            .check_words(self.variant.as_ref())
+            .map_err(CommandError::Parse)?;
+        let board = T::reset(self.variant.as_ref());
+        let warning = (session.tactic != board.name()).then(|| {
+            format!(
+                "warning: the session was saved with {}, but this solver is {}",
+                session.tactic,
+                board.name()
+            )
+        });
+        let board = mem::replace(&mut self.board, board);
+        let history = mem::take(&mut self.history);
+        let hard_mode = mem::replace(&mut self.hard_mode, session.hard_mode);
+        for (index, (word, status)) in session.rows.iter().enumerate() {
+            if let Err(error) = self.apply(*word, *status) {
+                self.board = board;
+                self.history = history;
+                self.hard_mode = hard_mode;
+                return Err(CommandError::Parse(format!(
+                    "row {}: {}",
+                    index + 1,
+                    error.message()
+                )));
+            }
+        }
+        Ok(warning)
+    }
+}
+
+#[derive(Debug)]
+pub enum CommandError {
+    Repl(ReplError),
+    Parse(String),
+    Feedback(FeedbackError),
+}
+
+impl From<ReplError> for CommandError {
+    fn from(error: ReplError) -> Self {
+        CommandError::Repl(error)
+    }
+}
+
+impl From<FeedbackError> for CommandError {
+    fn from(error: FeedbackError) -> Self {
+        CommandError::Feedback(error)
+    }
+}
+
+impl CommandError {
+    // 先頭のError:をつけないメッセージ
+    fn message(self) -> String {
+        match self {
+            CommandError::Parse(message) => message,
+            CommandError::Feedback(error) => error.to_string(),
+            error => error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::Repl(error) => write!(f, "{}", error),
+            CommandError::Parse(message) => write!(f, "Error: {}", message),
+            CommandError::Feedback(error) => write!(f, "Error: {}", error),
+        }
+    }
 }
 
 pub trait ReplFunctions: Solver + Sized {
-    fn reset() -> Self {
-        Self::new(CANDITATES.get_canditates(), CANDITATES.get_all_words())
+    fn reset(variant: &dyn Variant) -> Self {
+        let board = Self::new(variant.get_canditates(), variant.get_all_words());
+        match variant.priors() {
+            Some(priors) => board.with_priors(priors),
+            None => board,
+        }
     }
-    fn filter(word: Word, status: Status, board: &mut Self) {
+    fn filter(
+        word: Word,
+        status: Status,
+        board: &mut Self,
+        history: &[(Word, Status)],
+        dictionary: &[Word],
+    ) -> Result<(), FeedbackError> {
+        check_filter(history, board.remaining(), dictionary, &word, &status)?;
         board.filter(&word, &status);
+        Ok(())
     }
-    fn next(board: &mut Self) -> Word {
-        board.next()
+    fn next(board: &mut Self) -> Option<Word> {
+        if board.remaining().is_empty() {
+            return None;
+        }
+        Some(board.next())
     }
 }
 
@@ -26,31 +178,199 @@ pub trait ReplCommandHandlers: ReplFunctions {
     fn reset_handler(
         _args: HashMap<String, Value>,
         context: &mut ReplContext<Self>,
-    ) -> Result<Option<String>, ReplError> {
-        context.board = Self::reset();
+    ) -> Result<Option<String>, CommandError> {
+        context.board = Self::reset(context.variant.as_ref());
+        context.history.clear();
         Ok(None)
     }
     fn filter_handler(
         args: HashMap<String, Value>,
         context: &mut ReplContext<Self>,
-    ) -> Result<Option<String>, ReplError> {
+    ) -> Result<Option<String>, CommandError> {
         let word_string: String = args.get("word").unwrap().convert()?;
         let status_string: String = args.get("status").unwrap().convert()?;
-        let word: Word = word_string.parse().unwrap();
-        let status: Status = status_string.parse().unwrap();
-        let board = &mut context.board;
-        <Self as ReplFunctions>::filter(word, status, board);
-        Ok(None)
+        let word = context
+            .variant
+            .parse_word(&word_string)
+            .map_err(CommandError::Parse)?;
+        let status: Status = status_string.parse().map_err(CommandError::Parse)?;
+        context.apply(word, status)?;
+        // 残りの候補が罠になっていて、今の戦略で当てられない答えがあれば知らせる
+        let turns = context.max_guesses.saturating_sub(context.history.len());
+        let (variant, history) = (context.variant.as_ref(), &context.history);
+        let trap = detect(
+            context.board.remaining(),
+            &context.dictionary,
+            turns,
+            MIN_CLUSTER,
+            || {
+                let mut board = Self::reset(variant);
+                history
+                    .iter()
+                    .for_each(|(word, status)| board.filter(word, status));
+                board
+            },
+        );
+        Ok(trap.filter(|trap| trap.failure > 0.0).map(|trap| {
+            let shape = trap
+                .cluster
+                .shape(&variant.display_word(&trap.cluster.words[0]));
+            let probe = match trap.probe {
+                Some((word, groups)) => format!(
+                    "probe {} splits it into {}",
+                    variant.display_word(&word),
+                    groups
+                ),
+                None => "no probe splits it".to_string(),
+            };
+            format!(
+                "warning: trap {} ({} words), {:.0}% fail within {} guesses, {}",
+                shape,
+                trap.cluster.words.len(),
+                trap.failure * 100.0,
+                turns,
+                probe
+            )
+        }))
     }
     fn next_handler(
         _args: HashMap<String, Value>,
         context: &mut ReplContext<Self>,
-    ) -> Result<Option<String>, ReplError> {
+    ) -> Result<Option<String>, CommandError> {
+        Ok(Some(context.suggest()?))
+    }
+    fn knowledge_handler(
+        _args: HashMap<String, Value>,
+        context: &mut ReplContext<Self>,
+    ) -> Result<Option<String>, CommandError> {
+        let knowledge = Knowledge::from_history(&context.history);
+        Ok(Some(knowledge.to_summary(&context.variant.keyboard())))
+    }
+    fn canditates_handler(
+        args: HashMap<String, Value>,
+        context: &mut ReplContext<Self>,
+    ) -> Result<Option<String>, CommandError> {
+        let limit: usize = args.get("limit").unwrap().convert()?;
+        let remaining = context.board.remaining();
+        // 重みがなければどの候補も同じ確率
+        let priors = context
+            .variant
+            .priors()
+            .unwrap_or_else(|| Priors::new(HashMap::new(), 1.0));
+        let posterior = priors.posterior(remaining);
+        let mut lines: Vec<String> = posterior
+            .iter()
+            .take(limit)
+            .map(|(word, p)| format!("{} {:.4}", context.variant.display_word(word), p))
+            .collect();
+        lines.push(format!("canditates: {}", remaining.len()));
+        Ok(Some(lines.join("\n")))
+    }
+    fn score_handler(
+        args: HashMap<String, Value>,
+        context: &mut ReplContext<Self>,
+    ) -> Result<Option<String>, CommandError> {
+        let (partition, best) = Self::partitions(&args, context)?;
+        Ok(Some(format!(
+            "{}\nnext {}",
+            partition.summary(),
+            best.summary()
+        )))
+    }
+    fn explain_handler(
+        args: HashMap<String, Value>,
+        context: &mut ReplContext<Self>,
+    ) -> Result<Option<String>, CommandError> {
+        let (partition, best) = Self::partitions(&args, context)?;
+        let variant = &context.variant;
+        let mut lines: Vec<String> = partition
+            .buckets
+            .iter()
+            .map(|(status, words)| {
+                let examples: Vec<String> = words
+                    .iter()
+                    .take(3)
+                    .map(|w| variant.display_word(w))
+                    .collect();
+                format!(
+                    "{} {:>5} {:.3} {}{}",
+                    status,
+                    words.len(),
+                    partition.contribution(words.len()),
+                    examples.join(" "),
+                    if words.len() > 3 { " ..." } else { "" }
+                )
+            })
+            .collect();
+        lines.push(partition.summary());
+        lines.push(format!("next {}", best.summary()));
+        Ok(Some(lines.join("\n")))
+    }
+    fn hard_handler(
+        args: HashMap<String, Value>,
+        context: &mut ReplContext<Self>,
+    ) -> Result<Option<String>, CommandError> {
+        let mode: String = args.get("mode").unwrap().convert()?;
+        context.set_hard_mode(&mode)?;
+        Ok(None)
+    }
+    fn save_handler(
+        args: HashMap<String, Value>,
+        context: &mut ReplContext<Self>,
+    ) -> Result<Option<String>, CommandError> {
+        let path: String = args.get("path").unwrap().convert()?;
+        let session = Session {
+            tactic: context.board.name(),
+            words: fingerprint(context.variant.as_ref()),
+            hard_mode: context.hard_mode,
+            rows: context.history.clone(),
+        };
+        session
+            .save(context.variant.as_ref(), Path::new(&path))
+            .map_err(CommandError::Parse)?;
+        Ok(Some(format!("saved {} rows", session.rows.len())))
+    }
+    fn load_handler(
+        args: HashMap<String, Value>,
+        context: &mut ReplContext<Self>,
+    ) -> Result<Option<String>, CommandError> {
+        let path: String = args.get("path").unwrap().convert()?;
+        let session = Session::load(context.variant.as_ref(), Path::new(&path))
+            .map_err(CommandError::Parse)?;
+        let warning = context.restore(&session)?;
+        let loaded = format!(
+            "loaded {} rows, {} canditates remain",
+            context.history.len(),
+            context.board.remaining().len()
+        );
+        Ok(Some(match warning {
+            Some(warning) => format!("{}\n{}", warning, loaded),
+            None => loaded,
+        }))
+    }
+    // 入力した単語とsolverの一手のpartition
+    fn partitions(
+        args: &HashMap<String, Value>,
+        context: &mut ReplContext<Self>,
+    ) -> Result<(Partition, Partition), CommandError> {
+        let word_string: String = args.get("word").unwrap().convert()?;
+        let word = context
+            .variant
+            .parse_word(&word_string)
+            .map_err(CommandError::Parse)?;
         let board = &mut context.board;
-        let word = <Self as ReplFunctions>::next(board);
-        Ok(Some(word.to_string()))
+        let best = <Self as ReplFunctions>::next(board)
+            .ok_or_else(|| CommandError::Parse("no canditates remain".to_string()))?;
+        let remaining = context.board.remaining();
+        Ok((
+            Partition::new(&word, remaining),
+            Partition::new(&best, remaining),
+        ))
     }
-    fn into_repl() -> Repl<ReplContext<Self>, ReplError> {
+    fn into_repl(
+        variant: Box<dyn Variant>,
+        max_guesses: usize,
+    ) -> Repl<ReplContext<Self>, CommandError> {
         let reset_command =
             Command::new("reset", Self::reset_handler).with_help("Reset wordle solver state");
         let next_command =
@@ -61,15 +381,201 @@ pub trait ReplCommandHandlers: ReplFunctions {
             .unwrap()
             .with_parameter(Parameter::new("status").set_required(true).unwrap())
             .unwrap();
-        let ctx = ReplContext {
-            board: Self::reset(),
-        };
-        Repl::new(ctx)
+        let knowledge_command = Command::new("knowledge", Self::knowledge_handler)
+            .with_help("Show what is known about the answer so far");
+        let canditates_command = Command::new("canditates", Self::canditates_handler)
+            .with_help("Show remaining canditates with their probablity")
+            .with_parameter(Parameter::new("limit").set_default("10").unwrap())
+            .unwrap();
+        let score_command = Command::new("score", Self::score_handler)
+            .with_help("Score a word against the remaining canditates")
+            .with_parameter(Parameter::new("word").set_required(true).unwrap())
+            .unwrap();
+        let explain_command = Command::new("explain", Self::explain_handler)
+            .with_help("Show how a word splits the remaining canditates")
+            .with_parameter(Parameter::new("word").set_required(true).unwrap())
+            .unwrap();
+        let hard_command = Command::new("hard", Self::hard_handler)
+            .with_help("Turn hard mode on or off")
+            .with_parameter(Parameter::new("mode").set_required(true).unwrap())
+            .unwrap();
+        let save_command = Command::new("save", Self::save_handler)
+            .with_help("Save the session to a file")
+            .with_parameter(Parameter::new("path").set_required(true).unwrap())
+            .unwrap();
+        let load_command = Command::new("load", Self::load_handler)
+            .with_help("Load a session saved by save")
+            .with_parameter(Parameter::new("path").set_required(true).unwrap())
+            .unwrap();
+        Repl::new(ReplContext::new(variant).with_max_guesses(max_guesses))
             .with_name("wordle-solver")
             .with_version("v1.0.1")
             .with_description("Wordle solver")
             .add_command(reset_command)
             .add_command(next_command)
             .add_command(filter_command)
+            .add_command(knowledge_command)
+            .add_command(canditates_command)
+            .add_command(score_command)
+            .add_command(explain_command)
+            .add_command(hard_command)
+            .add_command(save_command)
+            .add_command(load_command)
+    }
+}
+
+// スクリプトモードで一行ごとに出す結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum ScriptOutput {
+    Reset,
+    Hard,
+    Filter { remaining: usize },
+    Next { word: String },
+}
+
+impl fmt::Display for ScriptOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScriptOutput::Reset | ScriptOutput::Hard => Ok(()),
+            ScriptOutput::Filter { remaining } => write!(f, "{}", remaining),
+            ScriptOutput::Next { word } => write!(f, "{}", word),
+        }
+    }
+}
+
+impl<T: ReplFunctions> ReplContext<T> {
+    // 空行と#から始まる行はNone
+    pub fn run_line(&mut self, line: &str) -> Result<Option<ScriptOutput>, CommandError> {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let output = match args.as_slice() {
+            [] => return Ok(None),
+            [first, ..] if first.starts_with('#') => return Ok(None),
+            ["reset"] => {
+                self.board = T::reset(self.variant.as_ref());
+                self.history.clear();
+                ScriptOutput::Reset
+            }
+            ["filter", word, status] => {
+                let word = self.variant.parse_word(word).map_err(CommandError::Parse)?;
+                let status: Status = status.parse().map_err(CommandError::Parse)?;
+                self.apply(word, status)?;
+                ScriptOutput::Filter {
+                    remaining: self.board.remaining().len(),
+                }
+            }
+            ["next"] => ScriptOutput::Next {
+                word: self.suggest()?,
+            },
+            ["hard", mode] => {
+                self.set_hard_mode(mode)?;
+                ScriptOutput::Hard
+            }
+            _ => {
+                return Err(CommandError::Parse(format!(
+                    "unknown command: {} (reset | filter <word> <status> | next | hard <on|off>)",
+                    line.trim()
+                )))
+            }
+        };
+        Ok(Some(output))
+    }
+}
+
+// reset/filter/nextを一行ずつ実行して、結果だけを書き出す。最初のエラーで止まる
+pub fn run_script<T: ReplFunctions>(
+    variant: Box<dyn Variant>,
+    input: impl BufRead,
+    mut output: impl Write,
+    json: bool,
+) -> Result<(), String> {
+    let mut context = ReplContext::<T>::new(variant);
+    for (index, line) in input.lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        let result = match context.run_line(&line) {
+            Ok(Some(result)) => result,
+            Ok(None) => continue,
+            Err(error) => {
+                let message = error.message();
+                if json {
+                    let error = serde_json::json!({ "line": index + 1, "error": message });
+                    writeln!(output, "{}", error).map_err(|e| e.to_string())?;
+                }
+                return Err(format!("line {}: {}", index + 1, message));
+            }
+        };
+        let written = if json {
+            writeln!(output, "{}", serde_json::to_string(&result).unwrap())
+        } else if matches!(result, ScriptOutput::Reset | ScriptOutput::Hard) {
+            Ok(())
+        } else {
+            writeln!(output, "{}", result)
+        };
+        written.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tactics::position_freq::Board, CANDITATES};
+    #[test]
+    fn script() {
+        let input = "# comment\nfilter soare __yy_\n\nnext\nreset\nfilter cigar ggggg\n";
+        let mut output = vec![];
+        run_script::<Board>(Box::new(&*CANDITATES), input.as_bytes(), &mut output, false).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "42");
+        assert_eq!(lines[2], "1");
+        let mut output = vec![];
+        let result = run_script::<Board>(
+            Box::new(&*CANDITATES),
+            "filter cigar ggggg\nfilter crane ggggg\nnext\n".as_bytes(),
+            &mut output,
+            true,
+        );
+        assert!(result.unwrap_err().starts_with("line 2:"));
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], r#"{"command":"filter","remaining":1}"#);
+        assert!(lines[1].starts_with(r#"{"error":"#));
+        assert_eq!(lines.len(), 2);
+        // スクリプトでもhard modeの確認をする
+        let mut output = vec![];
+        let result = run_script::<Board>(
+            Box::new(&*CANDITATES),
+            "hard on\nfilter soare g____\nfilter cigar _____\n".as_bytes(),
+            &mut output,
+            false,
+        );
+        assert!(result.unwrap_err().starts_with("line 3: hard mode"));
+    }
+    #[test]
+    fn restore() {
+        let mut context = ReplContext::<Board>::new(Box::new(&*CANDITATES));
+        let mut session = Session {
+            tactic: context.board.name(),
+            words: fingerprint(&*CANDITATES),
+            hard_mode: true,
+            rows: vec![
+                ("soare".parse().unwrap(), "g____".parse().unwrap()),
+                ("cigar".parse().unwrap(), "_____".parse().unwrap()),
+            ],
+        };
+        // hard modeではsを使わない行は読み込めず、元の状態のまま
+        let error = context.restore(&session).unwrap_err().to_string();
+        assert!(error.starts_with("Error: row 2: hard mode"), "{}", error);
+        assert!(!context.hard_mode && context.history.is_empty());
+        session.hard_mode = false;
+        assert_eq!(context.restore(&session).unwrap(), None);
+        assert_eq!(context.history, session.rows);
+        // 違う戦略で保存したものも、警告して読み込む
+        session.tactic = "avg_info_max".to_string();
+        let warning = context.restore(&session).unwrap().unwrap();
+        assert!(warning.contains("saved with avg_info_max"), "{}", warning);
+        assert_eq!(context.history, session.rows);
     }
 }
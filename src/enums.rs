@@ -1,104 +1,34 @@
-use itertools::{iproduct, Itertools};
-use std::str::FromStr;
+use crate::patterns::FEASIBLE_PATTERNS;
+use itertools::Itertools;
+use std::{fmt, str::FromStr};
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
-pub enum Alphabet {
-    A,
-    B,
-    C,
-    D,
-    E,
-    F,
-    G,
-    H,
-    I,
-    J,
-    K,
-    L,
-    M,
-    N,
-    O,
-    P,
-    Q,
-    R,
-    S,
-    T,
-    U,
-    V,
-    W,
-    X,
-    Y,
-    Z,
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash, PartialOrd, Ord)]
+pub struct Alphabet(pub char);
+
+impl Alphabet {
     // statusを計算するときだけ現れるやつ
-    Omega,
+    pub const OMEGA: Alphabet = Alphabet('_');
 }
 
-impl ToString for Alphabet {
-    fn to_string(&self) -> String {
-        match self {
-            Alphabet::A => "A",
-            Alphabet::B => "B",
-            Alphabet::C => "C",
-            Alphabet::D => "D",
-            Alphabet::E => "E",
-            Alphabet::F => "F",
-            Alphabet::G => "G",
-            Alphabet::H => "H",
-            Alphabet::I => "I",
-            Alphabet::J => "J",
-            Alphabet::K => "K",
-            Alphabet::L => "L",
-            Alphabet::M => "M",
-            Alphabet::N => "N",
-            Alphabet::O => "O",
-            Alphabet::P => "P",
-            Alphabet::Q => "Q",
-            Alphabet::R => "R",
-            Alphabet::S => "S",
-            Alphabet::T => "T",
-            Alphabet::U => "U",
-            Alphabet::V => "V",
-            Alphabet::W => "W",
-            Alphabet::X => "X",
-            Alphabet::Y => "Y",
-            Alphabet::Z => "Z",
-            Alphabet::Omega => "_",
-        }
-        .to_string()
-        .to_lowercase()
+impl fmt::Display for Alphabet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
 impl FromStr for Alphabet {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, String> {
-        match s.to_uppercase().as_ref() {
-            "A" => Ok(Alphabet::A),
-            "B" => Ok(Alphabet::B),
-            "C" => Ok(Alphabet::C),
-            "D" => Ok(Alphabet::D),
-            "E" => Ok(Alphabet::E),
-            "F" => Ok(Alphabet::F),
-            "G" => Ok(Alphabet::G),
-            "H" => Ok(Alphabet::H),
-            "I" => Ok(Alphabet::I),
-            "J" => Ok(Alphabet::J),
-            "K" => Ok(Alphabet::K),
-            "L" => Ok(Alphabet::L),
-            "M" => Ok(Alphabet::M),
-            "N" => Ok(Alphabet::N),
-            "O" => Ok(Alphabet::O),
-            "P" => Ok(Alphabet::P),
-            "Q" => Ok(Alphabet::Q),
-            "R" => Ok(Alphabet::R),
-            "S" => Ok(Alphabet::S),
-            "T" => Ok(Alphabet::T),
-            "U" => Ok(Alphabet::U),
-            "V" => Ok(Alphabet::V),
-            "W" => Ok(Alphabet::W),
-            "X" => Ok(Alphabet::X),
-            "Y" => Ok(Alphabet::Y),
-            "Z" => Ok(Alphabet::Z),
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_alphabetic() => {
+                // 大文字小文字のある文字は小文字に揃える（ßのように一文字にならないものはそのまま）
+                let mut lower = c.to_lowercase();
+                match (lower.next(), lower.next()) {
+                    (Some(l), None) => Ok(Alphabet(l)),
+                    _ => Ok(Alphabet(c)),
+                }
+            }
             _ => Err("not a char".to_string()),
         }
     }
@@ -115,22 +45,23 @@ impl FromStr for StatusChar {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, String> {
         match s {
-            "g" => Ok(StatusChar::Green),
-            "y" => Ok(StatusChar::Yellow),
-            "_" => Ok(StatusChar::Gray),
+            // 共有用の絵文字（ハイコントラストはオレンジとブルー）も受け付ける
+            "g" | "🟩" | "🟧" => Ok(StatusChar::Green),
+            "y" | "🟨" | "🟦" => Ok(StatusChar::Yellow),
+            "_" | "⬛" | "⬜" => Ok(StatusChar::Gray),
             _ => Err("not a valid status".to_string()),
         }
     }
 }
 
-impl ToString for StatusChar {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for StatusChar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = match self {
             StatusChar::Gray => "_",
             StatusChar::Yellow => "y",
             StatusChar::Green => "g",
-        }
-        .to_string()
+        };
+        write!(f, "{}", c)
     }
 }
 
@@ -140,22 +71,20 @@ pub struct Word(pub [Alphabet; 5]);
 impl FromStr for Word {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, String> {
-        let chars = s.chars();
-        let mut word = [Alphabet::A; 5];
-        chars.into_iter().enumerate().for_each(|(i, c)| {
-            word[i] = c.to_string().parse().unwrap();
-        });
+        if s.chars().count() != 5 {
+            return Err(format!("not a 5 letter word: {}", s));
+        }
+        let mut word = [Alphabet::OMEGA; 5];
+        for (i, c) in s.chars().enumerate() {
+            word[i] = c.to_string().parse()?;
+        }
         Ok(Word(word))
     }
 }
 
-impl ToString for Word {
-    fn to_string(&self) -> String {
-        self.0
-            .iter()
-            .map(|c| c.to_string())
-            .collect::<Vec<String>>()
-            .join("")
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.iter().try_for_each(|c| write!(f, "{}", c))
     }
 }
 
@@ -165,35 +94,29 @@ pub struct Status(pub [StatusChar; 5]);
 impl FromStr for Status {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, String> {
-        let chars = s.chars();
+        // 絵文字の後ろにつく異体字セレクタは無視する
+        let chars: Vec<char> = s.chars().filter(|c| *c != '\u{fe0f}').collect();
+        if chars.len() != 5 {
+            return Err(format!("not a 5 letter status: {}", s));
+        }
         let mut status = [StatusChar::Gray; 5];
-        chars.into_iter().enumerate().for_each(|(i, c)| {
-            status[i] = c.to_string().parse().unwrap();
-        });
+        for (i, c) in chars.into_iter().enumerate() {
+            status[i] = c.to_string().parse()?;
+        }
         Ok(Status(status))
     }
 }
 
-impl ToString for Status {
-    fn to_string(&self) -> String {
-        self.0
-            .iter()
-            .map(|c| c.to_string())
-            .collect::<Vec<String>>()
-            .join("")
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.iter().try_for_each(|c| write!(f, "{}", c))
     }
 }
 
 impl Status {
+    // 実際に出てくるstatusだけを返す
     pub fn get_status_iter() -> impl Iterator<Item = Status> {
-        iproduct!(
-            [StatusChar::Gray, StatusChar::Yellow, StatusChar::Green],
-            [StatusChar::Gray, StatusChar::Yellow, StatusChar::Green],
-            [StatusChar::Gray, StatusChar::Yellow, StatusChar::Green],
-            [StatusChar::Gray, StatusChar::Yellow, StatusChar::Green],
-            [StatusChar::Gray, StatusChar::Yellow, StatusChar::Green]
-        )
-        .map(|(a, b, c, d, e)| Status([a, b, c, d, e]))
+        FEASIBLE_PATTERNS.iter().map(|id| Status::from_id(*id))
     }
 }
 
@@ -206,7 +129,7 @@ impl Word {
         for word_idx in 0..5 {
             if word.0[word_idx] == answer.0[word_idx] {
                 status.0[word_idx] = StatusChar::Green;
-                answer.0[word_idx] = Alphabet::Omega;
+                answer.0[word_idx] = Alphabet::OMEGA;
             }
         }
         for word_idx in 0..5 {
@@ -218,7 +141,7 @@ impl Word {
                     .find_position(|a| *a == &word.0[word_idx])
                     .unwrap()
                     .0;
-                answer.0[pos] = Alphabet::Omega;
+                answer.0[pos] = Alphabet::OMEGA;
             }
         }
         status
@@ -242,4 +165,11 @@ mod tests {
         let status = Word::to_status(&word, &answer);
         assert_eq!(status.to_string(), "g____".to_string());
     }
+    #[test]
+    fn parse() {
+        assert!("abcdef".parse::<Word>().is_err());
+        assert!("ab1de".parse::<Word>().is_err());
+        assert_eq!("ÄRGER".parse::<Word>().unwrap().to_string(), "ärger");
+        assert!("gy_g".parse::<Status>().is_err());
+    }
 }
@@ -0,0 +1,317 @@
+use crate::{
+    enums::Status,
+    tactics::dynamic::DynBoard,
+    validation::FeedbackError,
+    variants::{language, LoadedVariant, Variant},
+    CANDITATES,
+};
+use std::{
+    ffi::{c_char, CStr},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+// C APIの戻り値。0以外はエラー
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordleError {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    UnknownTactic = 3,
+    UnknownLanguage = 4,
+    InvalidWordList = 5,
+    InvalidWord = 6,
+    InvalidStatus = 7,
+    // statusがその単語で出ない
+    Unproducible = 8,
+    // これまでの行と矛盾する
+    Conflict = 9,
+    NoCandidates = 10,
+    BufferTooSmall = 11,
+    Panic = 12,
+}
+
+impl From<FeedbackError> for WordleError {
+    fn from(error: FeedbackError) -> Self {
+        match error {
+            FeedbackError::Unproducible { .. } => WordleError::Unproducible,
+            FeedbackError::Conflict { .. } => WordleError::Conflict,
+            FeedbackError::NoCandidates { .. } => WordleError::NoCandidates,
+        }
+    }
+}
+
+// Cからは中身の見えないboard
+pub struct WordleBoard {
+    board: DynBoard,
+    variant: Box<dyn Variant>,
+}
+
+// panicをCに伝えないように、すべての関数をこれで包む
+fn guard(f: impl FnOnce() -> Result<(), WordleError>) -> WordleError {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => WordleError::Ok,
+        Ok(Err(error)) => error,
+        Err(_) => WordleError::Panic,
+    }
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> Result<&'a str, WordleError> {
+    if s.is_null() {
+        return Err(WordleError::NullPointer);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| WordleError::InvalidUtf8)
+}
+
+unsafe fn to_list<'a>(buffer: *const u8, len: usize) -> Result<&'a str, WordleError> {
+    if buffer.is_null() {
+        return Err(WordleError::NullPointer);
+    }
+    std::str::from_utf8(slice::from_raw_parts(buffer, len)).map_err(|_| WordleError::InvalidUtf8)
+}
+
+/// boardを作って`out`に入れる。`tactic`は"avg_info_max"、"position_freq"、"win_prob"のどれか。
+/// `answers`と`guesses`は一行一単語のUTF-8。`answers`がNULLなら埋め込みの英語の単語リストを使う。
+/// `language_name`は"english"、"kana"、"german"、"spanish"のどれか（NULLなら"english"）。
+///
+/// # Safety
+/// 文字列はNUL終端、バッファはその長さだけ読めること。作ったboardは`wordle_board_free`で解放する。
+#[no_mangle]
+pub unsafe extern "C" fn wordle_board_new(
+    tactic: *const c_char,
+    language_name: *const c_char,
+    answers: *const u8,
+    answers_len: usize,
+    guesses: *const u8,
+    guesses_len: usize,
+    out: *mut *mut WordleBoard,
+) -> WordleError {
+    guard(|| {
+        if out.is_null() {
+            return Err(WordleError::NullPointer);
+        }
+        let tactic = to_str(tactic)?;
+        let variant: Box<dyn Variant> = if answers.is_null() {
+            Box::new(&*CANDITATES)
+        } else {
+            let name = match language_name.is_null() {
+                true => "english",
+                false => to_str(language_name)?,
+            };
+            let language = language(name).ok_or(WordleError::UnknownLanguage)?;
+            let answers = to_list(answers, answers_len)?;
+            let guesses = match guesses.is_null() {
+                true => "",
+                false => to_list(guesses, guesses_len)?,
+            };
+            let variant = LoadedVariant::from_lists(language, answers, guesses)
+                .map_err(|_| WordleError::InvalidWordList)?;
+            Box::new(variant)
+        };
+        let board =
+            DynBoard::new(tactic, variant.as_ref()).map_err(|_| WordleError::UnknownTactic)?;
+        *out = Box::into_raw(Box::new(WordleBoard { board, variant }));
+        Ok(())
+    })
+}
+
+/// `wordle_board_new`で作ったboardを解放する。NULLなら何もしない。
+///
+/// # Safety
+/// 解放したboardはもう使わないこと。
+#[no_mangle]
+pub unsafe extern "C" fn wordle_board_free(board: *mut WordleBoard) {
+    if !board.is_null() {
+        drop(Box::from_raw(board));
+    }
+}
+
+/// 入力した単語とstatus（"__yy_"や絵文字）で絞り込む。これまでの行と矛盾するときはboardを変えない。
+///
+/// # Safety
+/// `board`は生きているboard、文字列はNUL終端であること。
+#[no_mangle]
+pub unsafe extern "C" fn wordle_board_filter(
+    board: *mut WordleBoard,
+    word: *const c_char,
+    status: *const c_char,
+) -> WordleError {
+    guard(|| {
+        let board = board.as_mut().ok_or(WordleError::NullPointer)?;
+        let word = board
+            .variant
+            .parse_word(to_str(word)?)
+            .map_err(|_| WordleError::InvalidWord)?;
+        let status: Status = to_str(status)?
+            .parse()
+            .map_err(|_| WordleError::InvalidStatus)?;
+        board.board.apply(&word, &status)?;
+        Ok(())
+    })
+}
+
+/// 次の単語をNUL終端のUTF-8で`buffer`に書く。
+///
+/// # Safety
+/// `board`は生きているboard、`buffer`は`buffer_len`バイト書けること。
+#[no_mangle]
+pub unsafe extern "C" fn wordle_board_next(
+    board: *const WordleBoard,
+    buffer: *mut c_char,
+    buffer_len: usize,
+) -> WordleError {
+    guard(|| {
+        let board = board.as_ref().ok_or(WordleError::NullPointer)?;
+        if buffer.is_null() {
+            return Err(WordleError::NullPointer);
+        }
+        let word = board.board.next().ok_or(WordleError::NoCandidates)?;
+        let word = board.variant.display_word(&word);
+        if word.len() + 1 > buffer_len {
+            return Err(WordleError::BufferTooSmall);
+        }
+        ptr::copy_nonoverlapping(word.as_ptr(), buffer as *mut u8, word.len());
+        *buffer.add(word.len()) = 0;
+        Ok(())
+    })
+}
+
+/// 残りの候補の数を`out`に書く。
+///
+/// # Safety
+/// `board`は生きているboard、`out`は書けること。
+#[no_mangle]
+pub unsafe extern "C" fn wordle_board_remaining(
+    board: *const WordleBoard,
+    out: *mut usize,
+) -> WordleError {
+    guard(|| {
+        let board = board.as_ref().ok_or(WordleError::NullPointer)?;
+        if out.is_null() {
+            return Err(WordleError::NullPointer);
+        }
+        *out = board.board.remaining().len();
+        Ok(())
+    })
+}
+
+/// 絞り込む前に戻す。
+///
+/// # Safety
+/// `board`は生きているboardであること。
+#[no_mangle]
+pub unsafe extern "C" fn wordle_board_reset(board: *mut WordleBoard) -> WordleError {
+    guard(|| {
+        let board = board.as_mut().ok_or(WordleError::NullPointer)?;
+        board.board.reset();
+        Ok(())
+    })
+}
+
+/// エラーコードの説明。静的なNUL終端の文字列を返す。
+#[no_mangle]
+pub extern "C" fn wordle_error_message(error: WordleError) -> *const c_char {
+    let message: &'static CStr = match error {
+        WordleError::Ok => c"ok",
+        WordleError::NullPointer => c"null pointer",
+        WordleError::InvalidUtf8 => c"invalid utf-8",
+        WordleError::UnknownTactic => c"unknown tactic",
+        WordleError::UnknownLanguage => c"unknown language",
+        WordleError::InvalidWordList => c"invalid word list",
+        WordleError::InvalidWord => c"invalid word",
+        WordleError::InvalidStatus => c"invalid status",
+        WordleError::Unproducible => c"the word cannot produce the status",
+        WordleError::Conflict => c"the row conflicts with earlier rows",
+        WordleError::NoCandidates => c"no candidates remain",
+        WordleError::BufferTooSmall => c"buffer too small",
+        WordleError::Panic => c"internal error",
+    };
+    message.as_ptr()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, path::Path};
+
+    const HEADER: &str = "ffi/wordle_solver.h";
+
+    // WORDLE_UPDATE_HEADER=1 cargo test header で作りなおす
+    #[test]
+    fn header() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let config = cbindgen::Config {
+            language: cbindgen::Language::C,
+            include_guard: Some("WORDLE_SOLVER_H".to_string()),
+            header: Some("/* generated by cbindgen from src/ffi.rs. do not edit. */".to_string()),
+            cpp_compat: true,
+            usize_is_size_t: true,
+            enumeration: cbindgen::EnumConfig {
+                rename_variants: cbindgen::RenameRule::QualifiedScreamingSnakeCase,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut generated = vec![];
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(root.join("src/ffi.rs"))
+            .generate()
+            .unwrap()
+            .write(&mut generated);
+        let generated = String::from_utf8(generated).unwrap();
+        if env::var_os("WORDLE_UPDATE_HEADER").is_some() {
+            std::fs::write(root.join(HEADER), &generated).unwrap();
+        }
+        let current = std::fs::read_to_string(root.join(HEADER)).unwrap_or_default();
+        assert_eq!(current, generated, "{} is out of date", HEADER);
+    }
+
+    #[test]
+    fn errors() {
+        unsafe {
+            let mut board = ptr::null_mut();
+            let tactic = c"position_freq".as_ptr();
+            let list = "cigar\nrebut\nsissy";
+            let error = wordle_board_new(
+                tactic,
+                c"klingon".as_ptr(),
+                list.as_ptr(),
+                list.len(),
+                ptr::null(),
+                0,
+                &mut board,
+            );
+            assert_eq!(error, WordleError::UnknownLanguage);
+            let error = wordle_board_new(
+                tactic,
+                ptr::null(),
+                list.as_ptr(),
+                list.len(),
+                ptr::null(),
+                0,
+                &mut board,
+            );
+            assert_eq!(error, WordleError::Ok);
+            let word = c"cigar".as_ptr();
+            let status = c"ggggy".as_ptr();
+            assert_eq!(
+                wordle_board_filter(board, word, status),
+                WordleError::Unproducible
+            );
+            let mut buffer = [0 as c_char; 3];
+            assert_eq!(
+                wordle_board_next(board, buffer.as_mut_ptr(), buffer.len()),
+                WordleError::BufferTooSmall
+            );
+            assert_eq!(
+                wordle_board_remaining(ptr::null(), ptr::null_mut()),
+                WordleError::NullPointer
+            );
+            wordle_board_free(board);
+        }
+    }
+}
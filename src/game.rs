@@ -0,0 +1,183 @@
+use crate::{
+    enums::{Status, Word},
+    knowledge::Knowledge,
+    tactics::solver::Solver,
+    variants::Variant,
+    CANDITATES,
+};
+use once_cell::sync::Lazy;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::HashSet, fmt, sync::Arc};
+
+static DICTIONARY: Lazy<Arc<HashSet<Word>>> =
+    Lazy::new(|| Arc::new(CANDITATES.get_all_words().into_iter().collect()));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuessError {
+    NotInWordList(Word),
+    HardMode(String),
+    GameOver,
+}
+
+impl fmt::Display for GuessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GuessError::NotInWordList(word) => write!(f, "{} is not in the word list", word),
+            GuessError::HardMode(message) => write!(f, "hard mode: {}", message),
+            GuessError::GameOver => write!(f, "the game is over"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Playing,
+    // 何回目で当たったか
+    Won(usize),
+    Lost,
+}
+
+pub struct Game {
+    answer: Word,
+    max_guesses: usize,
+    hard_mode: bool,
+    dictionary: Arc<HashSet<Word>>,
+    history: Vec<(Word, Status)>,
+}
+
+impl Game {
+    pub fn new(answer: Word, dictionary: Arc<HashSet<Word>>) -> Game {
+        Game {
+            answer,
+            max_guesses: 6,
+            hard_mode: false,
+            dictionary,
+            history: vec![],
+        }
+    }
+    // CANDITATESのindex番目が答え
+    pub fn from_index(index: usize) -> Option<Game> {
+        let answer = *CANDITATES.get_canditates().get(index)?;
+        Some(Game::new(answer, DICTIONARY.clone()))
+    }
+    // CANDITATESからseedで答えを選ぶ
+    pub fn random(seed: u64) -> Game {
+        let canditates = CANDITATES.get_canditates();
+        let index = StdRng::seed_from_u64(seed).gen_range(0..canditates.len());
+        Game::new(canditates[index], DICTIONARY.clone())
+    }
+    pub fn from_variant(variant: &dyn Variant, index: usize) -> Option<Game> {
+        let answer = *variant.get_canditates().get(index)?;
+        let dictionary = variant.get_all_words().into_iter().collect();
+        Some(Game::new(answer, Arc::new(dictionary)))
+    }
+    // 答えの候補がなければエラー
+    pub fn random_from_variant(variant: &dyn Variant, seed: u64) -> Result<Game, String> {
+        let len = variant.get_canditates().len();
+        if len == 0 {
+            return Err(format!("{} has no answers", variant.name()));
+        }
+        let index = StdRng::seed_from_u64(seed).gen_range(0..len);
+        Ok(Game::from_variant(variant, index).unwrap())
+    }
+    pub fn with_max_guesses(mut self, max_guesses: usize) -> Game {
+        self.max_guesses = max_guesses;
+        self
+    }
+    pub fn with_hard_mode(mut self, hard_mode: bool) -> Game {
+        self.hard_mode = hard_mode;
+        self
+    }
+    pub fn answer(&self) -> &Word {
+        &self.answer
+    }
+    pub fn max_guesses(&self) -> usize {
+        self.max_guesses
+    }
+    pub fn hard_mode(&self) -> bool {
+        self.hard_mode
+    }
+    pub fn history(&self) -> &[(Word, Status)] {
+        &self.history
+    }
+    pub fn state(&self) -> GameState {
+        match self.history.last() {
+            Some((word, _)) if *word == self.answer => GameState::Won(self.history.len()),
+            _ if self.history.len() >= self.max_guesses => GameState::Lost,
+            _ => GameState::Playing,
+        }
+    }
+    pub fn guess(&mut self, word: &Word) -> Result<Status, GuessError> {
+        if self.state() != GameState::Playing {
+            return Err(GuessError::GameOver);
+        }
+        if !self.dictionary.contains(word) {
+            return Err(GuessError::NotInWordList(*word));
+        }
+        if self.hard_mode {
+            Knowledge::from_history(&self.history)
+                .check_hard_mode(word)
+                .map_err(GuessError::HardMode)?;
+        }
+        let status = Word::to_status(word, &self.answer);
+        self.history.push((*word, status));
+        Ok(status)
+    }
+    // solverに最後まで解かせる。openerがあれば一手目はそれにする
+    pub fn autoplay<S: Solver>(
+        &mut self,
+        solver: &mut S,
+        opener: Option<&Word>,
+    ) -> Result<GameState, GuessError> {
+        while self.state() == GameState::Playing {
+            let word = match opener {
+                Some(opener) if self.history.is_empty() => *opener,
+                _ => solver.next_within(self.max_guesses - self.history.len()),
+            };
+            let status = self.guess(&word)?;
+            solver.filter(&word, &status);
+        }
+        Ok(self.state())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tactics::position_freq;
+    use crate::variants::{language, LoadedVariant};
+    #[test]
+    fn play() {
+        let mut game = Game::from_index(0).unwrap().with_hard_mode(true);
+        assert_eq!(game.answer().to_string(), "cigar");
+        let status = game.guess(&"soare".parse().unwrap()).unwrap();
+        assert_eq!(status.to_string(), "__yy_");
+        assert_eq!(
+            game.guess(&"qqqqq".parse().unwrap()),
+            Err(GuessError::NotInWordList("qqqqq".parse().unwrap()))
+        );
+        assert!(matches!(
+            game.guess(&"moult".parse().unwrap()),
+            Err(GuessError::HardMode(_))
+        ));
+        game.guess(&"cigar".parse().unwrap()).unwrap();
+        assert_eq!(game.state(), GameState::Won(2));
+        assert_eq!(
+            game.guess(&"cigar".parse().unwrap()),
+            Err(GuessError::GameOver)
+        );
+    }
+    #[test]
+    fn random_and_autoplay() {
+        assert_eq!(Game::random(42).answer(), Game::random(42).answer());
+        let mut game = Game::random(7).with_max_guesses(20);
+        let mut board =
+            position_freq::Board::new(CANDITATES.get_canditates(), CANDITATES.get_all_words());
+        let state = game.autoplay(&mut board, Some(&"soare".parse().unwrap()));
+        assert!(matches!(state, Ok(GameState::Won(_))));
+        // 答えの候補が空のリストではpanicしない
+        let empty = LoadedVariant::from_lists(language("kana").unwrap(), "", "あいうえお").unwrap();
+        assert!(Game::random_from_variant(&empty, 7).is_err());
+        assert!(Game::random_from_variant(&*CANDITATES, 7).is_ok());
+    }
+}
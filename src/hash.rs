@@ -0,0 +1,15 @@
+// 環境によって変わらないハッシュ。ファイルに保存する値に使う
+pub(crate) struct Fnv1a(pub(crate) u64);
+
+impl Fnv1a {
+    pub(crate) fn new() -> Fnv1a {
+        Fnv1a(0xcbf29ce484222325)
+    }
+    // 区切りに改行を入れる
+    pub(crate) fn feed(&mut self, s: &str) {
+        s.bytes().chain(std::iter::once(b'\n')).for_each(|b| {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        })
+    }
+}
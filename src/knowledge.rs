@@ -0,0 +1,199 @@
+use crate::enums::{Alphabet, Status, StatusChar, Word};
+use std::collections::{BTreeMap, BTreeSet};
+
+// これまでのfilterの結果からわかっていること
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Knowledge {
+    pub greens: [Option<Alphabet>; 5],
+    pub min_counts: BTreeMap<Alphabet, usize>,
+    pub exact_counts: BTreeMap<Alphabet, usize>,
+    pub forbidden: [BTreeSet<Alphabet>; 5],
+    pub excluded: BTreeSet<Alphabet>,
+}
+
+impl Knowledge {
+    pub fn new() -> Knowledge {
+        Knowledge::default()
+    }
+    pub fn from_history(history: &[(Word, Status)]) -> Knowledge {
+        let mut knowledge = Knowledge::new();
+        history
+            .iter()
+            .for_each(|(word, status)| knowledge.apply(word, status));
+        knowledge
+    }
+    pub fn apply(&mut self, word: &Word, status: &Status) {
+        for (index, (alphabet, status_char)) in word.0.iter().zip(status.0.iter()).enumerate() {
+            match status_char {
+                StatusChar::Green => self.greens[index] = Some(*alphabet),
+                // grayでもyellowでもその位置にその文字はない
+                StatusChar::Yellow | StatusChar::Gray => {
+                    self.forbidden[index].insert(*alphabet);
+                }
+            }
+        }
+        let letters: BTreeSet<Alphabet> = word.0.iter().cloned().collect();
+        for alphabet in letters {
+            let chars = word
+                .0
+                .iter()
+                .zip(status.0.iter())
+                .filter(|(a, _)| **a == alphabet)
+                .map(|(_, s)| *s);
+            let (found, gray) = chars.fold((0, false), |(found, gray), s| match s {
+                StatusChar::Gray => (found, true),
+                _ => (found + 1, gray),
+            });
+            let min = self.min_counts.entry(alphabet).or_insert(0);
+            *min = (*min).max(found);
+            if found == 0 && gray {
+                self.excluded.insert(alphabet);
+                self.min_counts.remove(&alphabet);
+            }
+            // grayが混じっていたら、その文字の個数は確定する
+            if gray {
+                self.exact_counts.insert(alphabet, found);
+            }
+        }
+    }
+    pub fn count_bounds(&self, alphabet: &Alphabet) -> (usize, Option<usize>) {
+        let min = *self.min_counts.get(alphabet).unwrap_or(&0);
+        (min, self.exact_counts.get(alphabet).cloned())
+    }
+    // knowledgeと矛盾しない単語か
+    pub fn allows(&self, word: &Word) -> bool {
+        let positions_ok = (0..5).all(|index| {
+            self.greens[index].is_none_or(|g| g == word.0[index])
+                && !self.forbidden[index].contains(&word.0[index])
+        });
+        positions_ok
+            && self
+                .min_counts
+                .iter()
+                .all(|(alphabet, min)| word.0.iter().filter(|a| *a == alphabet).count() >= *min)
+            && self
+                .exact_counts
+                .iter()
+                .all(|(alphabet, exact)| word.0.iter().filter(|a| *a == alphabet).count() == *exact)
+    }
+    // hard modeでは、greenの文字はその位置に、見つかった文字は個数分使わないといけない
+    pub fn check_hard_mode(&self, word: &Word) -> Result<(), String> {
+        for (index, green) in self.greens.iter().enumerate() {
+            if let Some(green) = green {
+                if word.0[index] != *green {
+                    return Err(format!("letter {} must be {}", index + 1, green));
+                }
+            }
+        }
+        for (alphabet, min) in self.min_counts.iter() {
+            if word.0.iter().filter(|a| *a == alphabet).count() < *min {
+                return Err(format!("guess must contain {}", alphabet));
+            }
+        }
+        Ok(())
+    }
+    // キーボード風のまとめ
+    // 位置が確定した文字は[x]、含まれる文字は(x)、含まれない文字は・で表示する
+    pub fn to_summary(&self, keyboard: &[String]) -> String {
+        let mut lines = vec![];
+        lines.push(format!(
+            "greens: {}",
+            self.greens
+                .iter()
+                .map(|g| g.map_or("_".to_string(), |a| a.to_string()))
+                .collect::<Vec<String>>()
+                .join(" ")
+        ));
+        keyboard.iter().enumerate().for_each(|(i, row)| {
+            let keys = row
+                .chars()
+                .map(|c| {
+                    let alphabet = Alphabet(c);
+                    if self.greens.contains(&Some(alphabet)) {
+                        format!("[{}]", c)
+                    } else if self.min_counts.contains_key(&alphabet) {
+                        format!("({})", c)
+                    } else if self.excluded.contains(&alphabet) {
+                        " ・".to_string()
+                    } else {
+                        format!(" {} ", c)
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join("");
+            lines.push(format!("{}{}", " ".repeat(i * 2), keys));
+        });
+        let present = self
+            .min_counts
+            .keys()
+            .map(|alphabet| {
+                let (min, exact) = self.count_bounds(alphabet);
+                let count = match exact {
+                    Some(exact) => format!("={}", exact),
+                    None => format!(">={}", min),
+                };
+                let banned = (0..5)
+                    .filter(|index| self.forbidden[*index].contains(alphabet))
+                    .map(|index| (index + 1).to_string())
+                    .collect::<Vec<String>>();
+                if banned.is_empty() {
+                    format!("{} {}", alphabet, count)
+                } else {
+                    format!("{} {} not {}", alphabet, count, banned.join(","))
+                }
+            })
+            .collect::<Vec<String>>();
+        lines.push(format!("present: {}", present.join(", ")));
+        lines.push(format!(
+            "excluded: {}",
+            self.excluded
+                .iter()
+                .map(|a| a.to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        ));
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CANDITATES;
+    fn row(word: &str, status: &str) -> (Word, Status) {
+        (word.parse().unwrap(), status.parse().unwrap())
+    }
+    #[test]
+    fn duplicated_letters() {
+        // 答えはabbey
+        let knowledge = Knowledge::from_history(&[row("bobby", "y_g_g"), row("ebbed", "_ggg_")]);
+        let b: Alphabet = "b".parse().unwrap();
+        let e: Alphabet = "e".parse().unwrap();
+        assert_eq!(knowledge.count_bounds(&b), (2, Some(2)));
+        assert_eq!(knowledge.count_bounds(&e), (1, Some(1)));
+        assert_eq!(knowledge.greens[2], Some(b));
+        assert!(knowledge.excluded.contains(&"o".parse().unwrap()));
+        assert!(knowledge.excluded.contains(&"d".parse().unwrap()));
+        assert!(knowledge.forbidden[0].contains(&b));
+        assert!(knowledge.allows(&"abbey".parse().unwrap()));
+        assert!(!knowledge.allows(&"bobby".parse().unwrap()));
+    }
+    #[test]
+    fn agrees_with_to_status() {
+        let answer: Word = "crate".parse().unwrap();
+        let history: Vec<(Word, Status)> = ["soare", "trace", "eerie"]
+            .iter()
+            .map(|w| {
+                let word: Word = w.parse().unwrap();
+                (word, Word::to_status(&word, &answer))
+            })
+            .collect();
+        let knowledge = Knowledge::from_history(&history);
+        CANDITATES.get_canditates().iter().for_each(|candidate| {
+            let consistent = history
+                .iter()
+                .all(|(word, status)| Word::to_status(word, candidate) == *status);
+            assert_eq!(knowledge.allows(candidate), consistent, "{}", candidate);
+        });
+    }
+}
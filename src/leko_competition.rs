@@ -1,2 +1,3 @@
 mod bridge;
 pub mod interface;
+pub mod json_lines;
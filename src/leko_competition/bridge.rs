@@ -1,8 +1,11 @@
 use crate::{
     enums::{Alphabet, Status, StatusChar, Word},
     leko_competition::interface::{Guesser, History, Response},
-    tactics::solver::Solver,
+    tactics::{avg_info_max::expected_info, solver::Solver},
+    validation::check_filter,
+    CANDITATES,
 };
+use once_cell::sync::Lazy;
 
 use super::interface::LekoRepl;
 
@@ -16,38 +19,85 @@ impl From<Response> for StatusChar {
     }
 }
 
-impl From<Vec<Response>> for Status {
-    fn from(responses: Vec<Response>) -> Self {
+impl TryFrom<Vec<Response>> for Status {
+    type Error = String;
+    fn try_from(responses: Vec<Response>) -> Result<Self, String> {
+        if responses.len() != 5 {
+            return Err(format!("expected 5 responses: {:?}", responses));
+        }
         let mut status = Status([StatusChar::Gray; 5]);
         for (i, response) in responses.into_iter().enumerate() {
             status.0[i] = response.into();
         }
-        status
+        Ok(status)
     }
 }
 
-impl From<Vec<char>> for Word {
-    fn from(chars: Vec<char>) -> Self {
-        let mut word = [Alphabet::A; 5];
+impl TryFrom<Vec<char>> for Word {
+    type Error = String;
+    fn try_from(chars: Vec<char>) -> Result<Self, String> {
+        if chars.len() != 5 {
+            return Err(format!(
+                "not a 5 letter word: {}",
+                String::from_iter(&chars)
+            ));
+        }
+        let mut word = [Alphabet::OMEGA; 5];
         for (i, c) in chars.into_iter().enumerate() {
-            word[i] = c.to_string().parse().unwrap();
+            word[i] = c.to_string().parse()?;
         }
-        Word(word)
+        Ok(Word(word))
+    }
+}
+
+static DICTIONARY: Lazy<Vec<Word>> = Lazy::new(|| CANDITATES.get_all_words());
+
+// 渡された行を確かめながらfilterする
+fn replay<S: Solver>(solver: &mut S, history: &[History]) -> Result<(), String> {
+    let mut rows = vec![];
+    for history in history.iter() {
+        let status = Status::try_from(history.response.clone())?;
+        let word = Word::try_from(history.word.clone())?;
+        check_filter(&rows, solver.remaining(), &DICTIONARY, &word, &status)
+            .map_err(|e| e.to_string())?;
+        solver.filter(&word, &status);
+        rows.push((word, status));
     }
+    Ok(())
+}
+
+fn opener<S: Solver>(solver: &S) -> String {
+    solver
+        .opener()
+        .map_or("soare".to_string(), |word| word.to_string())
 }
 
 impl<S: Solver> Guesser for S {
-    fn guess(&mut self, history: &Vec<History>) -> String {
+    fn guess(&mut self, history: &Vec<History>) -> Result<String, String> {
         if history.is_empty() {
-            return "soare".to_string();
+            return Ok(opener(self));
         }
-        for history in history.iter() {
-            let status = Status::from(history.response.clone());
-            let word = Word::from(history.word.clone());
-            self.filter(&word, &status);
+        replay(self, history)?;
+        Ok(self.next().to_string())
+    }
+    fn guess_within(&mut self, history: &Vec<History>, turns: usize) -> Result<String, String> {
+        if history.is_empty() {
+            return Ok(opener(self));
         }
-        self.next().to_string()
+        replay(self, history)?;
+        Ok(self.next_within(turns).to_string())
+    }
+    // 残りの候補を平均情報量で並べる
+    fn scores(&self, limit: usize) -> Vec<(String, f64)> {
+        let remaining = self.remaining();
+        let mut scores: Vec<(String, f64)> = remaining
+            .iter()
+            .map(|word| (word.to_string(), expected_info(word, remaining, None)))
+            .collect();
+        scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        scores.truncate(limit);
+        scores
     }
 }
 
-impl<S: Solver> LekoRepl for S {}
+impl<G: Guesser> LekoRepl for G {}
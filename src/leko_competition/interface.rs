@@ -1,8 +1,10 @@
 // this source code is modified from https://gist.github.com/Leko/125e92a263043debc36f5aa895bfd015
 // the reference implementation of leko competiion in rust
-use std::io;
+use serde::{Deserialize, Serialize};
+use std::{io, time::Duration};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Response {
     Absent,
     Present,
@@ -17,14 +19,70 @@ pub struct History {
 
 pub trait Guesser {
     #[allow(clippy::ptr_arg)]
-    fn guess(&mut self, history: &Vec<History>) -> String;
+    fn guess(&mut self, history: &Vec<History>) -> Result<String, String>;
+    // 残りturns手のうちに当てたいとき。手数を気にしないGuesserはguessと同じ
+    #[allow(clippy::ptr_arg)]
+    fn guess_within(&mut self, history: &Vec<History>, _turns: usize) -> Result<String, String> {
+        self.guess(history)
+    }
+    // 直前のguessのあとで、よさそうな単語とその点数。なければ空
+    fn scores(&self, _limit: usize) -> Vec<(String, f64)> {
+        vec![]
+    }
+}
+
+// 対局の始めに渡される設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameOptions {
+    pub hard_mode: bool,
+    pub max_guesses: usize,
+    // guessと一緒に返す点数の数
+    pub scores: usize,
+    // 一手にかけてよい時間
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        GameOptions {
+            hard_mode: false,
+            max_guesses: 6,
+            scores: 0,
+            time_limit: None,
+        }
+    }
+}
+
+// Guesserを外とつなぐやりとりの形式。対局ごとにnew_guesserでGuesserを作る
+pub trait Protocol {
+    fn run<G: Guesser>(&mut self, new_guesser: impl FnMut(&GameOptions) -> G)
+        -> Result<(), String>;
+}
+
+// もとのカンマ区切りの形式。一局だけ
+pub struct Leko;
+
+impl Protocol for Leko {
+    fn run<G: Guesser>(
+        &mut self,
+        mut new_guesser: impl FnMut(&GameOptions) -> G,
+    ) -> Result<(), String> {
+        new_guesser(&GameOptions::default()).run_repl();
+        Ok(())
+    }
 }
 
 pub trait LekoRepl: Guesser {
     #[allow(clippy::single_char_pattern)]
     fn run_repl(&mut self) {
         let mut history: Vec<History> = Vec::new();
-        let mut word = self.guess(&history);
+        let mut word = match self.guess(&history) {
+            Ok(word) => word,
+            Err(error) => {
+                eprintln!("error: {}", error);
+                return;
+            }
+        };
         println!("{}", word);
 
         loop {
@@ -57,7 +115,13 @@ pub trait LekoRepl: Guesser {
                         response,
                     });
                     eprintln!("{:?}", &history);
-                    word = self.guess(&history);
+                    word = match self.guess(&history) {
+                        Ok(word) => word,
+                        Err(error) => {
+                            eprintln!("error: {}", error);
+                            break;
+                        }
+                    };
                     println!("{}", word);
                 }
             }
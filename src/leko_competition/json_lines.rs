@@ -0,0 +1,243 @@
+use super::interface::{GameOptions, Guesser, History, Protocol, Response};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{self, BufRead, Write},
+    time::Duration,
+};
+
+fn default_max_guesses() -> usize {
+    GameOptions::default().max_guesses
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Row {
+    pub word: String,
+    pub response: Vec<Response>,
+}
+
+// 一行にひとつ届くメッセージ
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Start {
+        #[serde(default)]
+        hard_mode: bool,
+        #[serde(default = "default_max_guesses")]
+        max_guesses: usize,
+        #[serde(default)]
+        scores: usize,
+        #[serde(default)]
+        time_limit_ms: Option<u64>,
+    },
+    // これまでの行を渡されて次の単語を返す
+    History {
+        history: Vec<Row>,
+    },
+    End {
+        #[serde(default)]
+        answer: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Score {
+    pub word: String,
+    pub score: f64,
+}
+
+// リクエストひとつにつき一行返す
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Reply {
+    Ready,
+    Guess {
+        word: String,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        scores: Vec<Score>,
+    },
+    End {
+        games: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+pub struct JsonLines<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> JsonLines<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        JsonLines { input, output }
+    }
+    fn reply(&mut self, reply: &Reply) -> Result<(), String> {
+        let line = serde_json::to_string(reply).map_err(|e| e.to_string())?;
+        writeln!(self.output, "{}", line).map_err(|e| e.to_string())?;
+        self.output.flush().map_err(|e| e.to_string())
+    }
+}
+
+impl JsonLines<io::StdinLock<'static>, io::Stdout> {
+    pub fn stdio() -> Self {
+        JsonLines::new(io::stdin().lock(), io::stdout())
+    }
+}
+
+fn start_error(options: &GameOptions) -> Option<String> {
+    if options.hard_mode {
+        return Some("hard mode is not supported".to_string());
+    }
+    if options.max_guesses == 0 {
+        return Some("max_guesses must be positive".to_string());
+    }
+    None
+}
+
+impl<R: BufRead, W: Write> Protocol for JsonLines<R, W> {
+    // 壊れた行やGuesserのエラーはerrorを返して続ける
+    fn run<G: Guesser>(
+        &mut self,
+        mut new_guesser: impl FnMut(&GameOptions) -> G,
+    ) -> Result<(), String> {
+        let mut game: Option<(GameOptions, G)> = None;
+        let mut games = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.input.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+                return Ok(());
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let request: Request = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(error) => {
+                    self.reply(&Reply::Error {
+                        message: error.to_string(),
+                    })?;
+                    continue;
+                }
+            };
+            let reply = match request {
+                Request::Start {
+                    hard_mode,
+                    max_guesses,
+                    scores,
+                    time_limit_ms,
+                } => {
+                    let options = GameOptions {
+                        hard_mode,
+                        max_guesses,
+                        scores,
+                        time_limit: time_limit_ms.map(Duration::from_millis),
+                    };
+                    // hard modeで打てる単語だけから選ぶsolverはないので受けない
+                    match start_error(&options) {
+                        Some(message) => {
+                            game = None;
+                            Reply::Error { message }
+                        }
+                        None => {
+                            let guesser = new_guesser(&options);
+                            game = Some((options, guesser));
+                            Reply::Ready
+                        }
+                    }
+                }
+                Request::History { history } => {
+                    // startがなければ既定の設定で始める
+                    let (options, guesser) = game.get_or_insert_with(|| {
+                        let options = GameOptions::default();
+                        let guesser = new_guesser(&options);
+                        (options, guesser)
+                    });
+                    let history: Vec<History> = history
+                        .into_iter()
+                        .map(|row| History {
+                            word: row.word.chars().collect(),
+                            response: row.response,
+                        })
+                        .collect();
+                    let turns = options.max_guesses.saturating_sub(history.len());
+                    let guess = match turns {
+                        0 => Err(format!("no guesses left of {}", options.max_guesses)),
+                        _ => guesser.guess_within(&history, turns),
+                    };
+                    match guess {
+                        Ok(word) => Reply::Guess {
+                            word,
+                            scores: guesser
+                                .scores(options.scores)
+                                .into_iter()
+                                .map(|(word, score)| Score { word, score })
+                                .collect(),
+                        },
+                        Err(message) => Reply::Error { message },
+                    }
+                }
+                Request::End { .. } => {
+                    if game.take().is_some() {
+                        games += 1;
+                    }
+                    Reply::End { games }
+                }
+            };
+            self.reply(&reply)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tactics::position_freq::Board, tactics::solver::Solver, CANDITATES};
+    #[test]
+    fn json_lines() {
+        let input = [
+            r#"{"type":"start","scores":2,"time_limit_ms":1000}"#,
+            r#"{"type":"history","history":[]}"#,
+            r#"{"type":"history","history":[{"word":"soare","response":["absent","absent","present","present","absent"]}]}"#,
+            r#"not json"#,
+            r#"{"type":"history","history":[{"word":"so1re","response":["absent","absent","absent","absent","absent"]}]}"#,
+            r#"{"type":"end","answer":"cigar"}"#,
+            r#"{"type":"history","history":[{"word":"soare","response":["correct","correct","correct","correct","correct"]},{"word":"cigar","response":["correct","correct","correct","correct","correct"]}]}"#,
+            r#"{"type":"start","hard_mode":true}"#,
+            r#"{"type":"start","max_guesses":1}"#,
+            r#"{"type":"history","history":[{"word":"soare","response":["absent","absent","present","present","absent"]}]}"#,
+        ]
+        .join("\n");
+        let mut output = vec![];
+        JsonLines::new(input.as_bytes(), &mut output)
+            .run(|_| {
+                // 点数の計算が重いので答えの候補は一部だけ
+                let canditates = CANDITATES.get_canditates()[..200].to_vec();
+                Board::new(canditates, CANDITATES.get_all_words())
+            })
+            .unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0], r#"{"type":"ready"}"#);
+        assert!(lines[1].starts_with(r#"{"type":"guess","word":"soare","scores":"#));
+        assert!(lines[2].starts_with(r#"{"type":"guess","word":"#));
+        assert_eq!(lines[2].matches(r#""score":"#).count(), 2);
+        assert!(lines[3].starts_with(r#"{"type":"error""#));
+        // 文字でないものが来てもpanicしない
+        assert!(lines[4].starts_with(r#"{"type":"error""#));
+        assert_eq!(lines[5], r#"{"type":"end","games":1}"#);
+        assert!(lines[6].starts_with(r#"{"type":"error""#));
+        // 対応していない設定は受けず、手数の上限は守る
+        assert_eq!(
+            lines[7],
+            r#"{"type":"error","message":"hard mode is not supported"}"#
+        );
+        assert_eq!(lines[8], r#"{"type":"ready"}"#);
+        assert_eq!(
+            lines[9],
+            r#"{"type":"error","message":"no guesses left of 1"}"#
+        );
+    }
+}
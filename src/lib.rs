@@ -1,22 +1,96 @@
+// solverの途中経過をconsoleに出す。出力先がなければ何もしない
+macro_rules! console {
+    ($($arg:tt)*) => {
+        $crate::console_log(&format!($($arg)*))
+    };
+}
+
+pub mod coach;
 #[cfg(feature = "local")]
 mod commands;
-mod enums;
-mod leko_competition;
+pub mod enums;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ffi;
+pub mod game;
+mod hash;
+pub mod knowledge;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod leko_competition;
+pub mod patterns;
+pub mod priors;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod repls;
-mod tactics;
+pub mod reverse;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+pub mod session;
+pub mod share;
+pub mod tactics;
+pub mod tournament;
+pub mod traps;
+pub mod tree;
+pub mod validation;
+pub mod variants;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 mod words;
 
 use crate::words::Canditates;
 use once_cell::sync::Lazy;
-use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+use std::{cell::RefCell, rc::Rc, time::Duration};
 
 pub static CANDITATES: Lazy<Canditates> = Lazy::new(|| Canditates {});
 
-#[allow(clippy::mutex_atomic)]
-static SHOW_CONSOLE: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(false)));
+// 途中経過の出力先。wasmではJSのcallbackを渡す
+pub type Console = Rc<dyn Fn(&str)>;
+
+thread_local! {
+    static CONSOLE: RefCell<Option<Console>> = const { RefCell::new(None) };
+}
+
+pub fn set_console(console: Option<Console>) {
+    CONSOLE.with(|c| *c.borrow_mut() = console);
+}
 pub fn set_show_console(show: bool) {
-    *SHOW_CONSOLE.lock().unwrap() = show;
+    set_console(show.then(|| Rc::new(|line: &str| println!("{}", line)) as Console));
 }
 pub fn get_show_console() -> bool {
-    *SHOW_CONSOLE.lock().unwrap()
+    CONSOLE.with(|c| c.borrow().is_some())
+}
+pub fn console_log(line: &str) {
+    // callbackの中からまたconsoleを触っても大丈夫なように、借りたままにしない
+    if let Some(console) = CONSOLE.with(|c| c.borrow().clone()) {
+        console(line);
+    }
+}
+
+// 中でsolverを何度も動かすときは途中経過を出さない
+pub(crate) fn quietly<T>(f: impl FnOnce() -> T) -> T {
+    let console = CONSOLE.with(|c| c.borrow_mut().take());
+    let result = f();
+    set_console(console);
+    result
+}
+
+// wasm32ではInstantが使えないので、時間は0とする
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Stopwatch {
+        Stopwatch {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: Instant::now(),
+        }
+    }
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.start.elapsed();
+        #[cfg(target_arch = "wasm32")]
+        Duration::ZERO
+    }
 }
@@ -0,0 +1,403 @@
+use crate::enums::{Status, StatusChar, Word};
+use once_cell::sync::Lazy;
+use std::{cmp::Reverse, collections::HashMap, sync::Arc};
+
+// statusを3進数の番号にしたもの（gray=0, yellow=1, green=2、一文字目が一の位）
+pub type PatternId = u8;
+pub const PATTERN_COUNT: usize = 243;
+pub type Buckets = [u32; PATTERN_COUNT];
+
+// 四つがgreenで一つだけyellowになるstatusは出てこない
+pub static FEASIBLE_PATTERNS: Lazy<Vec<PatternId>> = Lazy::new(|| {
+    (0..PATTERN_COUNT as u16)
+        .map(|id| id as PatternId)
+        .filter(|id| {
+            let status = Status::from_id(*id);
+            let greens = status.0.iter().filter(|s| **s == StatusChar::Green).count();
+            let yellows = status
+                .0
+                .iter()
+                .filter(|s| **s == StatusChar::Yellow)
+                .count();
+            !(greens == 4 && yellows == 1)
+        })
+        .collect()
+});
+
+pub const ALL_GREEN: PatternId = (PATTERN_COUNT - 1) as PatternId;
+
+impl Status {
+    pub fn to_id(self) -> PatternId {
+        self.0.iter().rev().fold(0, |id, s| {
+            id * 3
+                + match s {
+                    StatusChar::Gray => 0,
+                    StatusChar::Yellow => 1,
+                    StatusChar::Green => 2,
+                }
+        })
+    }
+    pub fn from_id(id: PatternId) -> Status {
+        let mut status = Status([StatusChar::Gray; 5]);
+        let mut rest = id;
+        for s in status.0.iter_mut() {
+            *s = match rest % 3 {
+                0 => StatusChar::Gray,
+                1 => StatusChar::Yellow,
+                _ => StatusChar::Green,
+            };
+            rest /= 3;
+        }
+        status
+    }
+}
+
+// Word::to_statusと同じものを、Statusを作らずに計算する
+pub fn pattern_id(guess: &Word, answer: &Word) -> PatternId {
+    let mut marks = [0u8; 5];
+    let mut used = [false; 5];
+    for i in 0..5 {
+        if guess.0[i] == answer.0[i] {
+            marks[i] = 2;
+            used[i] = true;
+        }
+    }
+    for (mark, alphabet) in marks.iter_mut().zip(guess.0.iter()) {
+        if *mark != 0 {
+            continue;
+        }
+        if let Some(j) = (0..5).find(|&j| !used[j] && answer.0[j] == *alphabet) {
+            *mark = 1;
+            used[j] = true;
+        }
+    }
+    marks.iter().rev().fold(0, |id, m| id * 3 + m)
+}
+
+pub fn bucket_counts(guess: &Word, answers: &[Word]) -> Buckets {
+    let mut buckets = [0u32; PATTERN_COUNT];
+    answers
+        .iter()
+        .for_each(|answer| buckets[pattern_id(guess, answer) as usize] += 1);
+    buckets
+}
+
+// 答えごとの重みを足し合わせたバケツ
+pub fn weighted_buckets(guess: &Word, answers: &[Word], weights: &[f64]) -> [f64; PATTERN_COUNT] {
+    let mut buckets = [0f64; PATTERN_COUNT];
+    answers
+        .iter()
+        .zip(weights.iter())
+        .for_each(|(answer, weight)| buckets[pattern_id(guess, answer) as usize] += weight);
+    buckets
+}
+
+pub fn weighted_entropy(buckets: &[f64; PATTERN_COUNT]) -> f64 {
+    let total: f64 = buckets.iter().sum();
+    FEASIBLE_PATTERNS
+        .iter()
+        .map(|id| buckets[*id as usize])
+        .filter(|weight| *weight > 0.0)
+        .map(|weight| {
+            let probablity = weight / total;
+            -probablity * probablity.log2()
+        })
+        .sum()
+}
+
+// guessを入力したときに、答えの候補がstatusごとにどう分かれるか
+#[derive(Debug, Clone, PartialEq)]
+pub struct Partition {
+    pub guess: Word,
+    pub total: usize,
+    // 大きい順
+    pub buckets: Vec<(Status, Vec<Word>)>,
+}
+
+impl Partition {
+    pub fn new(guess: &Word, answers: &[Word]) -> Partition {
+        let ids: Vec<PatternId> = answers
+            .iter()
+            .map(|answer| pattern_id(guess, answer))
+            .collect();
+        Partition::from_ids(guess, answers, &ids)
+    }
+    // idsは答えごとのpattern。PatternMatrixの行からでも作れる
+    pub fn from_ids(guess: &Word, answers: &[Word], ids: &[PatternId]) -> Partition {
+        let mut groups: Vec<Vec<Word>> = vec![vec![]; PATTERN_COUNT];
+        answers
+            .iter()
+            .zip(ids)
+            .for_each(|(answer, id)| groups[*id as usize].push(*answer));
+        let mut buckets: Vec<(Status, Vec<Word>)> = groups
+            .into_iter()
+            .enumerate()
+            .filter(|(_, words)| !words.is_empty())
+            .map(|(id, words)| (Status::from_id(id as PatternId), words))
+            .collect();
+        buckets.sort_by_key(|(_, words)| Reverse(words.len()));
+        Partition {
+            guess: *guess,
+            total: answers.len(),
+            buckets,
+        }
+    }
+    // バケツ一つ分の情報量への寄与
+    pub fn contribution(&self, size: usize) -> f64 {
+        let probablity = size as f64 / self.total as f64;
+        -probablity * probablity.log2()
+    }
+    pub fn entropy(&self) -> f64 {
+        self.buckets
+            .iter()
+            .map(|(_, words)| self.contribution(words.len()))
+            .sum()
+    }
+    // 入力したあとに残る候補の数の期待値
+    pub fn expected_remaining(&self) -> f64 {
+        self.buckets
+            .iter()
+            .map(|(_, words)| (words.len() * words.len()) as f64)
+            .sum::<f64>()
+            / self.total as f64
+    }
+    pub fn worst_case(&self) -> usize {
+        self.buckets.first().map_or(0, |(_, words)| words.len())
+    }
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: info {:.3}, expected remaining {:.2}, worst case {}, buckets {}",
+            self.guess,
+            self.entropy(),
+            self.expected_remaining(),
+            self.worst_case(),
+            self.buckets.len()
+        )
+    }
+}
+
+// バケツの大きさから平均情報量を計算する
+pub fn entropy(buckets: &Buckets) -> f64 {
+    let total = buckets.iter().sum::<u32>() as f64;
+    FEASIBLE_PATTERNS
+        .iter()
+        .map(|id| buckets[*id as usize])
+        .filter(|count| *count != 0)
+        .map(|count| {
+            let probablity = count as f64 / total;
+            -probablity * probablity.log2()
+        })
+        .sum()
+}
+
+// すべての入力とすべての答えの組のpattern idを前もって計算したもの
+pub struct PatternMatrix {
+    guesses: Vec<Word>,
+    answers: Vec<Word>,
+    guess_index: HashMap<Word, usize>,
+    answer_index: HashMap<Word, usize>,
+    ids: Vec<PatternId>,
+}
+
+impl PatternMatrix {
+    pub fn new(guesses: Vec<Word>, answers: Vec<Word>) -> PatternMatrix {
+        let ids = guesses
+            .iter()
+            .flat_map(|guess| answers.iter().map(move |answer| pattern_id(guess, answer)))
+            .collect();
+        let index = |words: &[Word]| words.iter().enumerate().map(|(i, w)| (*w, i)).collect();
+        PatternMatrix {
+            guess_index: index(&guesses),
+            answer_index: index(&answers),
+            guesses,
+            answers,
+            ids,
+        }
+    }
+    pub fn guesses(&self) -> &[Word] {
+        &self.guesses
+    }
+    pub fn answers(&self) -> &[Word] {
+        &self.answers
+    }
+    pub fn guess_index(&self, guess: &Word) -> Option<usize> {
+        self.guess_index.get(guess).copied()
+    }
+    pub fn answer_index(&self, answer: &Word) -> Option<usize> {
+        self.answer_index.get(answer).copied()
+    }
+    // guess番目の入力に対する、すべての答えのpattern id
+    pub fn row(&self, guess: usize) -> &[PatternId] {
+        let len = self.answers.len();
+        &self.ids[guess * len..(guess + 1) * len]
+    }
+    pub fn get(&self, guess: usize, answer: usize) -> PatternId {
+        self.ids[guess * self.answers.len() + answer]
+    }
+    // answersは答えの番号
+    pub fn buckets(&self, guess: usize, answers: &[usize]) -> Buckets {
+        let row = self.row(guess);
+        let mut buckets = [0u32; PATTERN_COUNT];
+        answers
+            .iter()
+            .for_each(|answer| buckets[row[*answer] as usize] += 1);
+        buckets
+    }
+    // guessを入力してstatusが出たときに残る答えの番号
+    pub fn filter(&self, guess: usize, status: &Status, answers: &[usize]) -> Vec<usize> {
+        let id = status.to_id();
+        let row = self.row(guess);
+        answers
+            .iter()
+            .filter(|answer| row[**answer] == id)
+            .copied()
+            .collect()
+    }
+}
+
+// PatternMatrixの表を使って、絞り込むたびに入力ごとのバケツを更新していくもの
+pub struct LiveBuckets {
+    matrix: Arc<PatternMatrix>,
+    // 残っている答えの番号（表の答えの順）
+    answers: Vec<usize>,
+    buckets: Vec<Buckets>,
+}
+
+impl LiveBuckets {
+    pub fn new(matrix: Arc<PatternMatrix>) -> LiveBuckets {
+        let answers: Vec<usize> = (0..matrix.answers().len()).collect();
+        let buckets = (0..matrix.guesses().len())
+            .map(|guess| matrix.buckets(guess, &answers))
+            .collect();
+        LiveBuckets {
+            matrix,
+            answers,
+            buckets,
+        }
+    }
+    pub fn matrix(&self) -> &PatternMatrix {
+        &self.matrix
+    }
+    pub fn answers(&self) -> &[usize] {
+        &self.answers
+    }
+    // guess番目の入力で残りの答えがどう分かれるか
+    pub fn buckets(&self, guess: usize) -> &Buckets {
+        &self.buckets[guess]
+    }
+    // 表にない単語でも絞り込める。消えた答えと残った答えの少ないほうだけバケツを数えなおす
+    pub fn filter(&mut self, guess: &Word, status: &Status) {
+        let id = status.to_id();
+        let (kept, removed): (Vec<usize>, Vec<usize>) = match self.matrix.guess_index(guess) {
+            Some(index) => {
+                let row = self.matrix.row(index);
+                self.answers.iter().partition(|answer| row[**answer] == id)
+            }
+            None => self
+                .answers
+                .iter()
+                .partition(|answer| pattern_id(guess, &self.matrix.answers()[**answer]) == id),
+        };
+        let matrix = &self.matrix;
+        self.buckets
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, buckets)| {
+                let row = matrix.row(index);
+                if removed.len() <= kept.len() {
+                    removed
+                        .iter()
+                        .for_each(|answer| buckets[row[*answer] as usize] -= 1);
+                } else {
+                    *buckets = [0; PATTERN_COUNT];
+                    kept.iter()
+                        .for_each(|answer| buckets[row[*answer] as usize] += 1);
+                }
+            });
+        self.answers = kept;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CANDITATES;
+    #[test]
+    fn ids() {
+        assert_eq!(FEASIBLE_PATTERNS.len(), 238);
+        assert!(!FEASIBLE_PATTERNS.contains(&"ggggy".parse::<Status>().unwrap().to_id()));
+        assert_eq!("ggggg".parse::<Status>().unwrap().to_id(), ALL_GREEN);
+        (0..PATTERN_COUNT as u16).for_each(|id| {
+            assert_eq!(Status::from_id(id as PatternId).to_id(), id as PatternId);
+        });
+    }
+    #[test]
+    fn same_as_to_status() {
+        let answers = CANDITATES.get_canditates();
+        ["soare", "speed", "eerie", "llama", "abbey"]
+            .iter()
+            .map(|w| w.parse::<Word>().unwrap())
+            .for_each(|guess| {
+                answers.iter().for_each(|answer| {
+                    assert_eq!(
+                        pattern_id(&guess, answer),
+                        Word::to_status(&guess, answer).to_id()
+                    );
+                });
+                let buckets = bucket_counts(&guess, &answers);
+                assert_eq!(buckets.iter().sum::<u32>() as usize, answers.len());
+                let weighted = weighted_buckets(&guess, &answers, &vec![1.0; answers.len()]);
+                assert!((weighted_entropy(&weighted) - entropy(&buckets)).abs() < 1e-9);
+                let partition = Partition::new(&guess, &answers);
+                assert!((partition.entropy() - entropy(&buckets)).abs() < 1e-9);
+                assert_eq!(
+                    partition.worst_case() as u32,
+                    *buckets.iter().max().unwrap()
+                );
+            });
+    }
+    #[test]
+    fn matrix() {
+        let answers = CANDITATES.get_canditates()[..50].to_vec();
+        let guesses = CANDITATES.get_all_words()[..80].to_vec();
+        let matrix = PatternMatrix::new(guesses.clone(), answers.clone());
+        let guess = matrix.guess_index(&guesses[7]).unwrap();
+        assert_eq!(matrix.get(guess, 3), pattern_id(&guesses[7], &answers[3]));
+        let all: Vec<usize> = (0..answers.len()).collect();
+        assert_eq!(
+            matrix.buckets(guess, &all),
+            bucket_counts(&guesses[7], &answers)
+        );
+        assert_eq!(
+            Partition::from_ids(&guesses[7], &answers, matrix.row(guess)),
+            Partition::new(&guesses[7], &answers)
+        );
+        let status = Word::to_status(&guesses[7], &answers[3]);
+        let remaining = matrix.filter(guess, &status, &all);
+        assert!(remaining.contains(&3));
+        assert!(remaining
+            .iter()
+            .all(|a| Word::to_status(&guesses[7], &answers[*a]) == status));
+    }
+    #[test]
+    fn live_buckets() {
+        let answers = CANDITATES.get_canditates()[..60].to_vec();
+        let guesses = CANDITATES.get_all_words()[..90].to_vec();
+        let mut live = LiveBuckets::new(Arc::new(PatternMatrix::new(
+            guesses.clone(),
+            answers.clone(),
+        )));
+        let mut remaining = answers.clone();
+        // 表にある単語と表にない単語で絞り込んで、毎回数えなおしたものと同じになる
+        for guess in [guesses[11], "soare".parse().unwrap(), guesses[40]] {
+            let status = Word::to_status(&guess, &remaining[remaining.len() / 2]);
+            live.filter(&guess, &status);
+            remaining.retain(|answer| Word::to_status(&guess, answer) == status);
+            let words: Vec<Word> = live.answers().iter().map(|a| answers[*a]).collect();
+            assert_eq!(words, remaining);
+            guesses.iter().enumerate().for_each(|(index, word)| {
+                assert_eq!(live.buckets(index), &bucket_counts(word, &remaining));
+            });
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{enums::Word, variants::Variant};
+use std::{collections::HashMap, fs, path::Path};
+
+// 単語ごとの答えになりやすさ（正規化はしない）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Priors {
+    weights: HashMap<Word, f64>,
+    default_weight: f64,
+}
+
+impl Priors {
+    pub fn new(weights: HashMap<Word, f64>, default_weight: f64) -> Priors {
+        Priors {
+            weights,
+            default_weight,
+        }
+    }
+    // よく使われる順に並んだ単語に、順位のシグモイドで重みをつける
+    pub fn sigmoid_over_rank(ranked: &[Word], midpoint: f64, width: f64) -> Priors {
+        let sigmoid = |rank: f64| 1.0 / (1.0 + ((rank - midpoint) / width).exp());
+        let mut weights = HashMap::new();
+        ranked.iter().enumerate().for_each(|(rank, word)| {
+            weights.entry(*word).or_insert(sigmoid(rank as f64));
+        });
+        Priors::new(weights, sigmoid(ranked.len() as f64))
+    }
+    // 一行に「単語 出現回数」、どの行にも回数がなければ順位のシグモイドにする
+    pub fn parse(variant: &dyn Variant, text: &str) -> Result<Priors, String> {
+        let mut words = vec![];
+        let mut counts = vec![];
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+            let mut columns = line.split_whitespace();
+            let word = variant.parse_word(columns.next().unwrap()).map_err(error)?;
+            // 回数のある行とない行が混ざっていたらエラー
+            match (columns.next(), words.len() == counts.len()) {
+                (Some(count), true) => {
+                    let count: f64 = count
+                        .parse()
+                        .map_err(|_| error(format!("not a frequency: {}", line)))?;
+                    if !count.is_finite() || count <= 0.0 {
+                        return Err(error(format!("frequency must be positive: {}", line)));
+                    }
+                    counts.push(count);
+                }
+                (None, _) if counts.is_empty() => {}
+                _ => return Err(error(format!("some lines have no frequency: {}", line))),
+            }
+            words.push(word);
+        }
+        if words.is_empty() {
+            return Err("no words in priors file".to_string());
+        }
+        if counts.is_empty() {
+            let midpoint = words.len() as f64 / 2.0;
+            return Ok(Priors::sigmoid_over_rank(&words, midpoint, midpoint / 10.0));
+        }
+        let min = counts.iter().cloned().fold(f64::INFINITY, f64::min);
+        let weights = words.into_iter().zip(counts).collect();
+        Ok(Priors::new(weights, min.min(1.0) / 2.0))
+    }
+    pub fn load(variant: &dyn Variant, path: &Path) -> Result<Priors, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Priors::parse(variant, &text)
+    }
+    pub fn weight(&self, word: &Word) -> f64 {
+        *self.weights.get(word).unwrap_or(&self.default_weight)
+    }
+    pub fn weights(&self, words: &[Word]) -> Vec<f64> {
+        words.iter().map(|word| self.weight(word)).collect()
+    }
+    // 残りの候補の中で正規化した確率（大きい順）
+    pub fn posterior(&self, remaining: &[Word]) -> Vec<(Word, f64)> {
+        let total: f64 = remaining.iter().map(|word| self.weight(word)).sum();
+        let mut posterior: Vec<(Word, f64)> = remaining
+            .iter()
+            .map(|word| (*word, self.weight(word) / total))
+            .collect();
+        posterior.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap());
+        posterior
+    }
+}
+
+// 重みつきの単語リスト。all_answersなら入力できる単語すべてを答えの候補にする
+pub struct Weighted<V: Variant> {
+    pub variant: V,
+    pub priors: Priors,
+    pub all_answers: bool,
+}
+
+impl<V: Variant> Variant for Weighted<V> {
+    fn name(&self) -> &str {
+        self.variant.name()
+    }
+    fn letters(&self) -> Vec<char> {
+        self.variant.letters()
+    }
+    fn get_canditates(&self) -> Vec<Word> {
+        if self.all_answers {
+            self.variant.get_all_words()
+        } else {
+            self.variant.get_canditates()
+        }
+    }
+    fn get_all_words(&self) -> Vec<Word> {
+        self.variant.get_all_words()
+    }
+    fn normalize(&self, s: &str) -> String {
+        self.variant.normalize(s)
+    }
+    fn display_word(&self, word: &Word) -> String {
+        self.variant.display_word(word)
+    }
+    fn keyboard(&self) -> Vec<String> {
+        self.variant.keyboard()
+    }
+    fn priors(&self) -> Option<Priors> {
+        Some(self.priors.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CANDITATES;
+    #[test]
+    fn parse() {
+        let priors = Priors::parse(&*CANDITATES, "about 100\nabbey 10\n").unwrap();
+        let about: Word = "about".parse().unwrap();
+        let abbey: Word = "abbey".parse().unwrap();
+        let zymic: Word = "zymic".parse().unwrap();
+        let posterior = priors.posterior(&[abbey, zymic, about]);
+        assert_eq!(posterior[0].0, about);
+        assert_eq!(posterior[2].0, zymic);
+        assert!((posterior.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+        let ranked = Priors::parse(&*CANDITATES, "about\nabbey\ncigar\n").unwrap();
+        assert!(ranked.weight(&about) > ranked.weight(&abbey));
+        assert!(ranked.weight(&abbey) > ranked.weight(&zymic));
+        // 回数が0以下のものや、回数のある行とない行が混ざったものは読まない
+        let error = Priors::parse(&*CANDITATES, "about 0\nabbey 0\n").unwrap_err();
+        assert!(error.starts_with("line 1:"));
+        assert!(Priors::parse(&*CANDITATES, "about 3\nabbey -1\n").is_err());
+        assert!(Priors::parse(&*CANDITATES, "about 3\nabbey inf\n").is_err());
+        let error = Priors::parse(&*CANDITATES, "about 3\n\nabbey\n").unwrap_err();
+        assert!(error.starts_with("line 3:"));
+        assert!(Priors::parse(&*CANDITATES, "about\nabbey 3\n").is_err());
+        // 単語が一つもないファイルは読まない
+        assert!(Priors::parse(&*CANDITATES, "").is_err());
+        assert!(Priors::parse(&*CANDITATES, "# comment\n\n").is_err());
+    }
+}
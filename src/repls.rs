@@ -6,42 +6,469 @@ use crate::{
 #[cfg(feature = "local")]
 pub mod local {
     use super::*;
-    use crate::commands::{ReplCommandHandlers, ReplFunctions};
+    use crate::{
+        coach::analyze,
+        commands::{run_script, ReplCommandHandlers, ReplFunctions},
+        enums::{Status, StatusChar, Word},
+        game::{Game, GameState},
+        reverse::{possible_guesses, rank_answers},
+        share::{ShareGrid, Theme},
+        tactics::{endgame::Endgame, solver::Solver, win_prob},
+        tournament::Tournament,
+        traps::trap_prone,
+        tree::{DecisionTree, Format},
+        variants::Variant,
+        Console,
+    };
+    use std::{
+        fs::File,
+        io::{self, BufRead, BufReader, Read, Write},
+        path::Path,
+        process,
+        rc::Rc,
+    };
 
     impl ReplFunctions for avg_info_max::Board {}
     impl ReplCommandHandlers for avg_info_max::Board {}
     impl ReplFunctions for position_freq::Board {}
     impl ReplCommandHandlers for position_freq::Board {}
+    impl ReplFunctions for win_prob::Board {}
+    impl ReplCommandHandlers for win_prob::Board {}
+    impl<S: Solver> ReplFunctions for Endgame<S> {}
+    impl<S: Solver> ReplCommandHandlers for Endgame<S> {}
 
-    pub fn avg_info_max() {
-        set_show_console(true);
-        let mut repl = avg_info_max::Board::into_repl();
-        repl.run().unwrap();
+    // 戦略の型が決まってからすること。with_tacticで名前から型を選んで呼ぶ
+    trait TacticAction {
+        type Output;
+        fn run<S: ReplCommandHandlers>(self) -> Self::Output;
     }
 
-    pub fn position_freq() {
+    // 戦略の名前と、終盤の探索で包むかどうかから型を選ぶ。知らない名前ならErr
+    fn with_tactic<A: TacticAction>(
+        name: &str,
+        endgame: bool,
+        action: A,
+    ) -> Result<A::Output, String> {
+        match (name, endgame) {
+            ("position_freq", false) => Ok(action.run::<position_freq::Board>()),
+            ("avg_info_max", false) => Ok(action.run::<avg_info_max::Board>()),
+            ("win_prob", false) => Ok(action.run::<win_prob::Board>()),
+            ("position_freq", true) => Ok(action.run::<Endgame<position_freq::Board>>()),
+            ("avg_info_max", true) => Ok(action.run::<Endgame<avg_info_max::Board>>()),
+            ("win_prob", true) => Ok(action.run::<Endgame<win_prob::Board>>()),
+            _ => Err(format!("unknown tactic: {}", name)),
+        }
+    }
+
+    // 戦略を選んで動かすサブコマンド
+    pub enum Command<'a> {
+        Repl {
+            max_guesses: usize,
+        },
+        Coach {
+            answer: Word,
+            guesses: Vec<Word>,
+        },
+        Script {
+            path: Option<&'a Path>,
+            json: bool,
+        },
+        Traps {
+            first: Option<Word>,
+            min_size: usize,
+            max_guesses: usize,
+        },
+        Tree {
+            first: Option<Word>,
+            format: Format,
+            max_guesses: usize,
+        },
+    }
+
+    struct Run<'a> {
+        variant: Box<dyn Variant>,
+        command: Command<'a>,
+    }
+
+    impl TacticAction for Run<'_> {
+        type Output = ();
+        fn run<S: ReplCommandHandlers>(self) {
+            let variant = self.variant;
+            match self.command {
+                Command::Repl { max_guesses } => repl::<S>(variant, max_guesses),
+                Command::Coach { answer, guesses } => coach::<S>(variant, answer, guesses),
+                Command::Script { path, json } => script::<S>(variant, path, json),
+                Command::Traps {
+                    first,
+                    min_size,
+                    max_guesses,
+                } => traps::<S>(variant, first, min_size, max_guesses),
+                Command::Tree {
+                    first,
+                    format,
+                    max_guesses,
+                } => tree::<S>(variant, first, format, max_guesses),
+            }
+        }
+    }
+
+    // tacticの名前で選んだ戦略（endgameなら終盤の探索で包む）でcommandを動かす
+    pub fn run(
+        variant: Box<dyn Variant>,
+        tactic: &str,
+        endgame: bool,
+        command: Command,
+    ) -> Result<(), String> {
+        with_tactic(tactic, endgame, Run { variant, command })
+    }
+
+    pub fn repl<S: ReplCommandHandlers>(variant: Box<dyn Variant>, max_guesses: usize) {
         set_show_console(true);
-        let mut repl = position_freq::Board::into_repl();
+        let mut repl = S::into_repl(variant, max_guesses);
         repl.run().unwrap();
     }
+
+    // 背景色をつけて一文字ずつ表示する
+    pub fn render_row(variant: &dyn Variant, word: &Word, status: &Status) -> String {
+        variant
+            .display_word(word)
+            .chars()
+            .zip(status.0.iter())
+            .map(|(c, s)| {
+                let color = match s {
+                    StatusChar::Green => "42",
+                    StatusChar::Yellow => "43",
+                    StatusChar::Gray => "100",
+                };
+                format!("\x1b[30;{}m {} \x1b[0m", color, c)
+            })
+            .collect()
+    }
+
+    pub fn play(variant: Box<dyn Variant>, mut game: Game) {
+        println!(
+            "guess the word in {} tries{}. quit with Ctrl+D",
+            game.max_guesses(),
+            if game.hard_mode() { " (hard mode)" } else { "" }
+        );
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        while game.state() == GameState::Playing {
+            print!("{}/{}> ", game.history().len() + 1, game.max_guesses());
+            io::stdout().flush().unwrap();
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                _ => break,
+            };
+            let result = variant
+                .parse_word(&line)
+                .and_then(|word| game.guess(&word).map_err(|e| e.to_string()));
+            match result {
+                Ok(_) => game.history().iter().for_each(|(word, status)| {
+                    println!("{}", render_row(variant.as_ref(), word, status));
+                }),
+                Err(message) => println!("{}", message),
+            }
+        }
+        match game.state() {
+            GameState::Won(count) => println!("solved in {}/{}", count, game.max_guesses()),
+            _ => println!("answer: {}", variant.display_word(game.answer())),
+        }
+        if !game.history().is_empty() {
+            println!(
+                "\n{}",
+                ShareGrid::from_game("Wordle", &game).render(Theme::Dark)
+            );
+        }
+    }
+
+    // 終わったゲームを一手ずつ評価する
+    pub fn coach<S: Solver>(variant: Box<dyn Variant>, answer: Word, guesses: Vec<Word>) {
+        set_show_console(false);
+        let board = S::new(variant.get_canditates(), variant.get_all_words());
+        let reports = analyze(board, &guesses, &answer);
+        reports.iter().for_each(|report| println!("{}", report));
+        let skill = reports.iter().map(|r| r.skill).sum::<f64>() / reports.len().max(1) as f64;
+        let luck = reports.iter().map(|r| r.luck).sum::<f64>();
+        println!("skill: {:.0}, luck: {:+.2}", skill, luck);
+    }
+
+    // 全部の答えを解いて、途中で罠に入った答えを罠の大きい順に出す
+    pub fn traps<S: Solver>(
+        variant: Box<dyn Variant>,
+        first: Option<Word>,
+        min_size: usize,
+        max_guesses: usize,
+    ) {
+        set_show_console(false);
+        let mut cases = trap_prone::<S>(
+            &variant.get_canditates(),
+            &variant.get_all_words(),
+            first.as_ref(),
+            max_guesses,
+            min_size,
+        );
+        cases.sort_by(|a, b| b.cluster.cmp(&a.cluster).then(a.turn.cmp(&b.turn)));
+        cases.iter().for_each(|case| {
+            println!(
+                "{} turn {} cluster {}{}",
+                variant.display_word(&case.answer),
+                case.turn,
+                case.cluster,
+                if case.failed { " failed" } else { "" }
+            )
+        });
+        let failed = cases.iter().filter(|case| case.failed).count();
+        println!("trapped: {}, failed: {}", cases.len(), failed);
+    }
+
+    // 全部の答えを解いた決定木を出す。まとめは標準エラーに出す
+    pub fn tree<S: Solver>(
+        variant: Box<dyn Variant>,
+        first: Option<Word>,
+        format: Format,
+        max_guesses: usize,
+    ) {
+        set_show_console(false);
+        let tree = match DecisionTree::build::<S>(
+            &variant.get_canditates(),
+            &variant.get_all_words(),
+            first.as_ref(),
+            max_guesses,
+        ) {
+            Ok(tree) => tree,
+            Err(message) => {
+                eprintln!("{}", message);
+                process::exit(1);
+            }
+        };
+        print!("{}", tree.export(variant.as_ref(), format));
+        eprintln!(
+            "answers: {}, average: {:.4}, depth: {}, failed: {}",
+            tree.root.answers,
+            tree.average(),
+            tree.depth(),
+            tree.failures().len()
+        );
+    }
+
+    // 名前のうしろの+endgameを一つだけ外す
+    fn split_endgame(tactic: &str) -> (&str, bool) {
+        match tactic.strip_suffix("+endgame") {
+            Some(name) => (name, true),
+            None => (tactic, false),
+        }
+    }
+
+    // 名前を確かめるだけ
+    struct Check;
+
+    impl TacticAction for Check {
+        type Output = ();
+        fn run<S: ReplCommandHandlers>(self) {}
+    }
+
+    struct Enter<'a>(&'a mut Tournament);
+
+    impl TacticAction for Enter<'_> {
+        type Output = ();
+        fn run<S: ReplCommandHandlers>(self) {
+            self.0.run::<S>();
+        }
+    }
+
+    // tacticsを同じ答えとopenerで比べる。「position_freq+endgame」のように+endgameをつけられる
+    pub fn tournament(
+        variant: Box<dyn Variant>,
+        tactics: &[&str],
+        first: Option<Word>,
+        max_guesses: usize,
+        json: bool,
+    ) {
+        // 走らせる前に、知らない名前がないか確かめる
+        if let Err(message) = tactics.iter().try_for_each(|tactic| {
+            let (name, endgame) = split_endgame(tactic);
+            with_tactic(name, endgame, Check).map_err(|_| format!("unknown tactic: {}", tactic))
+        }) {
+            return eprintln!("{}", message);
+        }
+        // 途中経過は標準エラーに出す
+        crate::set_console(Some(Rc::new(|line: &str| eprintln!("{}", line)) as Console));
+        let mut tournament = Tournament::new(variant.get_canditates(), variant.get_all_words())
+            .with_opener(first)
+            .with_max_guesses(max_guesses);
+        for tactic in tactics {
+            let (name, endgame) = split_endgame(tactic);
+            with_tactic(name, endgame, Enter(&mut tournament)).unwrap();
+        }
+        match json {
+            true => println!("{}", tournament.to_json()),
+            false => print!("{}", tournament.to_markdown()),
+        }
+    }
+
+    // 木を答えの候補と照らし合わせる。問題があれば終了コード1
+    pub fn verify(variant: Box<dyn Variant>, path: &Path) {
+        let tree = match DecisionTree::load(variant.as_ref(), path) {
+            Ok(tree) => tree,
+            Err(message) => {
+                eprintln!("{}", message);
+                process::exit(1);
+            }
+        };
+        let verification = tree.verify(&variant.get_canditates(), &variant.get_all_words());
+        let words = |words: &[Word]| {
+            words
+                .iter()
+                .map(|word| variant.display_word(word))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        println!("answers: {}", tree.root.answers);
+        println!(
+            "missing: {} {}",
+            verification.missing.len(),
+            words(&verification.missing)
+        );
+        println!(
+            "unknown: {} {}",
+            verification.unknown.len(),
+            words(&verification.unknown)
+        );
+        println!(
+            "duplicates: {} {}",
+            verification.duplicates.len(),
+            words(&verification.duplicates)
+        );
+        verification.inconsistent.iter().for_each(|(answer, turn)| {
+            println!(
+                "inconsistent: {} turn {}",
+                variant.display_word(answer),
+                turn
+            )
+        });
+        println!(
+            "not in word list: {} {}",
+            verification.not_in_word_list.len(),
+            words(&verification.not_in_word_list)
+        );
+        println!(
+            "failed: {} {}",
+            verification.failed.len(),
+            words(&verification.failed)
+        );
+        println!("depth: {}", verification.depth);
+        println!("average: {:.4}", verification.average);
+        if !verification.is_ok(tree.max_guesses) {
+            process::exit(1);
+        }
+    }
+
+    // pathがなければ標準入力から読む。エラーのときは終了コード1
+    pub fn script<S: ReplFunctions>(variant: Box<dyn Variant>, path: Option<&Path>, json: bool) {
+        set_show_console(false);
+        let stdout = io::stdout();
+        let result = match path {
+            Some(path) => File::open(path)
+                .map_err(|e| format!("{}: {}", path.display(), e))
+                .and_then(|file| {
+                    run_script::<S>(variant, BufReader::new(file), stdout.lock(), json)
+                }),
+            None => run_script::<S>(variant, io::stdin().lock(), stdout.lock(), json),
+        };
+        if let Err(message) = result {
+            eprintln!("{}", message);
+            process::exit(1);
+        }
+    }
+
+    // 標準入力に貼られたshare gridから答えを推測する。answerがあれば各行で入力できた単語を出す
+    pub fn reverse(variant: Box<dyn Variant>, answer: Option<Word>) {
+        let mut text = String::new();
+        if let Err(e) = io::stdin().read_to_string(&mut text) {
+            return eprintln!("{}", e);
+        }
+        let grids = match ShareGrid::parse_many(&text) {
+            Ok(grids) => grids,
+            Err(message) => return eprintln!("{}", message),
+        };
+        let dictionary = variant.get_all_words();
+        if let Some(answer) = answer {
+            for grid in grids.iter() {
+                println!("{}", grid.title);
+                let guesses = possible_guesses(&answer, &grid.rows, &dictionary);
+                for (status, words) in grid.rows.iter().zip(guesses.iter()) {
+                    let shown: Vec<String> = words
+                        .iter()
+                        .take(10)
+                        .map(|w| variant.display_word(w))
+                        .collect();
+                    println!("{} {} {}", status, words.len(), shown.join(" "));
+                }
+            }
+            return;
+        }
+        let rows: Vec<Vec<Status>> = grids.into_iter().map(|grid| grid.rows).collect();
+        let ranked = rank_answers(&rows, &variant.get_canditates(), &dictionary);
+        println!("{} answers", ranked.len());
+        ranked.iter().take(20).for_each(|inference| {
+            println!(
+                "{} {:.2} {:?}",
+                variant.display_word(&inference.answer),
+                inference.score,
+                inference.row_counts
+            )
+        });
+    }
 }
 
 pub mod leko_competition {
-    use crate::{leko_competition::interface::LekoRepl, tactics::solver::Solver, CANDITATES};
+    use crate::{
+        leko_competition::{
+            interface::{Leko, Protocol},
+            json_lines::JsonLines,
+        },
+        tactics::{budget::TimeLimited, lookup::Lookup, solver::Solver},
+        tree::DecisionTree,
+        CANDITATES,
+    };
+    use std::{sync::Arc, time::Duration};
 
     use super::*;
 
     pub fn position_freq() {
-        set_show_console(false);
-        let mut board =
-            position_freq::Board::new(CANDITATES.get_canditates(), CANDITATES.get_all_words());
-        board.run_repl();
+        leko::<position_freq::Board>(None, None);
     }
 
     pub fn avg_info_max() {
+        leko::<avg_info_max::Board>(None, None);
+    }
+
+    // treeがあれば引いて打ち、木にない局面では一手ごとにtime_limitで探索を打ち切る
+    pub fn leko<S: Solver>(time_limit: Option<Duration>, tree: Option<Arc<DecisionTree>>) {
+        set_show_console(false);
+        let result = Leko.run(|_| {
+            let board =
+                TimeLimited::<S>::new(CANDITATES.get_canditates(), CANDITATES.get_all_words())
+                    .with_time_limit(time_limit);
+            Lookup::wrap(board).with_tree(tree.clone())
+        });
+        if let Err(message) = result {
+            eprintln!("error: {}", message);
+        }
+    }
+
+    // JSON Linesで何局でも続けて受ける。startで時間を渡されたらそちらを使う
+    pub fn json_lines<S: Solver>(time_limit: Option<Duration>, tree: Option<Arc<DecisionTree>>) {
         set_show_console(false);
-        let mut board =
-            avg_info_max::Board::new(CANDITATES.get_canditates(), CANDITATES.get_all_words());
-        board.run_repl();
+        let result = JsonLines::stdio().run(|options| {
+            let board =
+                TimeLimited::<S>::new(CANDITATES.get_canditates(), CANDITATES.get_all_words())
+                    .with_time_limit(options.time_limit.or(time_limit));
+            Lookup::wrap(board).with_tree(tree.clone())
+        });
+        if let Err(message) = result {
+            eprintln!("error: {}", message);
+        }
     }
 }
@@ -0,0 +1,114 @@
+use crate::{
+    enums::{Status, Word},
+    patterns::{pattern_id, ALL_GREEN, PATTERN_COUNT},
+};
+
+// answerが答えのときに、statusを出す単語
+pub fn producing_words(answer: &Word, status: &Status, dictionary: &[Word]) -> Vec<Word> {
+    let id = status.to_id();
+    dictionary
+        .iter()
+        .filter(|guess| pattern_id(guess, answer) == id)
+        .cloned()
+        .collect()
+}
+
+// 答えを決めたときに、それぞれの行で入力できた単語
+pub fn possible_guesses(answer: &Word, rows: &[Status], dictionary: &[Word]) -> Vec<Vec<Word>> {
+    rows.iter()
+        .map(|status| producing_words(answer, status, dictionary))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inference {
+    pub answer: Word,
+    // 行ごとの、その行を出せる単語の数
+    pub row_counts: Vec<Vec<u32>>,
+    // 入力をランダムに選んだとしたときの対数尤度
+    pub score: f64,
+}
+
+// すべてのgridのすべての行を出せる答えを、もっともらしい順に並べる
+pub fn rank_answers(
+    grids: &[Vec<Status>],
+    canditates: &[Word],
+    dictionary: &[Word],
+) -> Vec<Inference> {
+    let total = dictionary.len() as f64;
+    let mut inferences: Vec<Inference> = canditates
+        .iter()
+        .filter_map(|answer| {
+            // 答えから見たバケツなので、入力と答えを入れ替えて数える
+            let mut counts = [0u32; PATTERN_COUNT];
+            dictionary
+                .iter()
+                .for_each(|guess| counts[pattern_id(guess, answer) as usize] += 1);
+            let row_counts: Vec<Vec<u32>> = grids
+                .iter()
+                .map(|rows| rows.iter().map(|s| counts[s.to_id() as usize]).collect())
+                .collect();
+            if row_counts.iter().flatten().any(|count| *count == 0) {
+                return None;
+            }
+            let score = grids
+                .iter()
+                .zip(row_counts.iter())
+                .flat_map(|(rows, counts)| rows.iter().zip(counts.iter()))
+                .filter(|(status, _)| status.to_id() != ALL_GREEN)
+                .map(|(_, count)| (*count as f64 / total).ln())
+                .sum();
+            Some(Inference {
+                answer: *answer,
+                row_counts,
+                score,
+            })
+        })
+        .collect();
+    inferences.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+    inferences
+}
+
+// 一枚のgridだけのときの、答えの候補
+pub fn consistent_answers(rows: &[Status], canditates: &[Word], dictionary: &[Word]) -> Vec<Word> {
+    rank_answers(&[rows.to_vec()], canditates, dictionary)
+        .into_iter()
+        .map(|inference| inference.answer)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CANDITATES;
+    fn rows(answer: &str, guesses: &[&str]) -> Vec<Status> {
+        let answer: Word = answer.parse().unwrap();
+        guesses
+            .iter()
+            .map(|g| Word::to_status(&g.parse().unwrap(), &answer))
+            .collect()
+    }
+    #[test]
+    fn reverse() {
+        // 時間がかかるので答えの候補は先頭の一部だけ
+        let canditates = CANDITATES.get_canditates()[..100].to_vec();
+        let dictionary = CANDITATES.get_all_words();
+        let answer: Word = "cigar".parse().unwrap();
+        let grid = rows("cigar", &["soare", "trial", "cigar"]);
+        let answers = consistent_answers(&grid, &canditates, &dictionary);
+        assert!(answers.contains(&answer));
+        let guesses = possible_guesses(&answer, &grid, &dictionary);
+        assert!(guesses[1].contains(&"trial".parse().unwrap()));
+        assert_eq!(guesses[2], vec![answer]);
+        // 何人ものgridを合わせると絞れる
+        let grids = vec![
+            grid,
+            rows("cigar", &["crane", "chair", "cigar"]),
+            rows("cigar", &["audio", "grail", "cigar"]),
+            rows("cigar", &["fight", "rigor", "cigar"]),
+        ];
+        let ranked = rank_answers(&grids, &canditates, &dictionary);
+        assert!(ranked.len() < answers.len());
+        assert!(ranked.iter().any(|i| i.answer == answer));
+    }
+}
@@ -0,0 +1,473 @@
+use crate::{
+    enums::{Status, Word},
+    patterns::{Partition, PatternMatrix},
+    priors::Priors,
+    tactics::{
+        avg_info_max::{self, matrix_info},
+        position_freq,
+        solver::Solver,
+        win_prob,
+    },
+    validation::check_filter,
+    variants::Variant,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+type HttpError = (u16, String);
+
+// これより大きい本文は読まずに413を返す
+const MAX_BODY: usize = 64 * 1024;
+// ヘッダーの一行の長さと行数の上限。読み終わらない接続はREAD_TIMEOUTで切る
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn bad_request(message: impl ToString) -> HttpError {
+    (400, message.to_string())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Row {
+    pub word: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SuggestRequest {
+    #[serde(default)]
+    pub history: Vec<Row>,
+    pub tactic: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FilterRequest {
+    #[serde(default)]
+    pub history: Vec<Row>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExplainRequest {
+    #[serde(default)]
+    pub history: Vec<Row>,
+    pub word: String,
+}
+
+// 起動時に単語リストとpatternの表を一度だけ作り、すべてのリクエストで使い回す
+pub struct Service {
+    variant: Box<dyn Variant>,
+    matrix: PatternMatrix,
+    priors: Option<Priors>,
+    max_guesses: usize,
+}
+
+impl Service {
+    pub fn new(variant: Box<dyn Variant>) -> Service {
+        let matrix = PatternMatrix::new(variant.get_all_words(), variant.get_canditates());
+        let priors = variant.priors();
+        Service {
+            variant,
+            matrix,
+            priors,
+            max_guesses: 6,
+        }
+    }
+    pub fn with_max_guesses(mut self, max_guesses: usize) -> Service {
+        self.max_guesses = max_guesses;
+        self
+    }
+    // 状態は持たないので、毎回historyから絞り込みなおす
+    fn remaining(&self, history: &[Row]) -> Result<Vec<usize>, HttpError> {
+        let mut rows = vec![];
+        let mut remaining: Vec<usize> = (0..self.matrix.answers().len()).collect();
+        for row in history {
+            let word = self.variant.parse_word(&row.word).map_err(bad_request)?;
+            let status: Status = row.status.parse().map_err(bad_request)?;
+            let guess = self
+                .matrix
+                .guess_index(&word)
+                .ok_or_else(|| bad_request(format!("{} is not in the word list", word)))?;
+            let answers = self.words(&remaining);
+            check_filter(&rows, &answers, self.matrix.guesses(), &word, &status)
+                .map_err(|e| (422, e.to_string()))?;
+            remaining = self.matrix.filter(guess, &status, &remaining);
+            rows.push((word, status));
+        }
+        Ok(remaining)
+    }
+    fn words(&self, answers: &[usize]) -> Vec<Word> {
+        answers.iter().map(|i| self.matrix.answers()[*i]).collect()
+    }
+    // 残りturns手で当てる一手。手数を気にしない戦略はnextと同じ
+    fn next<S: Solver>(&self, remaining: Vec<Word>, turns: usize) -> Word {
+        let board = S::new(remaining, self.matrix.guesses().to_vec());
+        match &self.priors {
+            Some(priors) => board.with_priors(priors.clone()).next_within(turns),
+            None => board.next_within(turns),
+        }
+    }
+    // 入力ごとの、残りの答えについての平均情報量。答えの確率があれば重みをつける
+    fn infos(&self, remaining: &[usize]) -> Vec<f64> {
+        let weights = self
+            .priors
+            .as_ref()
+            .map(|priors| priors.weights(&self.words(remaining)));
+        (0..self.matrix.guesses().len())
+            .map(|guess| matrix_info(&self.matrix, guess, remaining, weights.as_deref()))
+            .collect()
+    }
+    // 共有している表から選ぶ。avg_info_max::Boardと同じく、同じ情報量なら先の単語
+    fn next_avg_info_max(&self, remaining: &[usize], infos: &[f64], turns: usize) -> Word {
+        if remaining.len() <= 2 {
+            return self.next::<avg_info_max::Board>(self.words(remaining), turns);
+        }
+        let (guess, _) = infos
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .unwrap();
+        self.matrix.guesses()[guess]
+    }
+    pub fn suggest(&self, request: SuggestRequest) -> Result<Value, HttpError> {
+        // historyの行の数だけ手を使っている
+        let turns = self.max_guesses.saturating_sub(request.history.len());
+        if turns == 0 {
+            return Err((422, format!("no guesses left of {}", self.max_guesses)));
+        }
+        let remaining = self.remaining(&request.history)?;
+        let words = self.words(&remaining);
+        let infos = self.infos(&remaining);
+        let tactic = request.tactic.as_deref().unwrap_or("avg_info_max");
+        let next = match tactic {
+            "avg_info_max" => self.next_avg_info_max(&remaining, &infos, turns),
+            "position_freq" => self.next::<position_freq::Board>(words, turns),
+            "win_prob" => self.next::<win_prob::Board>(words, turns),
+            _ => return Err(bad_request(format!("unknown tactic: {}", tactic))),
+        };
+        let mut is_remaining = vec![false; self.matrix.answers().len()];
+        remaining.iter().for_each(|i| is_remaining[*i] = true);
+        let mut suggestions: Vec<(Word, f64, bool)> = self
+            .matrix
+            .guesses()
+            .iter()
+            .enumerate()
+            .map(|(i, guess)| {
+                let info = infos[i];
+                let answer = self
+                    .matrix
+                    .answer_index(guess)
+                    .is_some_and(|a| is_remaining[a]);
+                (*guess, info, answer)
+            })
+            .collect();
+        // tacticによらず平均情報量の順。同じ情報量なら答えになりうる単語を先に
+        suggestions.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(b.2.cmp(&a.2)));
+        suggestions.truncate(request.limit.unwrap_or(10));
+        Ok(json!({
+            "tactic": tactic,
+            "remaining": remaining.len(),
+            "next": self.variant.display_word(&next),
+            "ranked_by": "info",
+            "suggestions": suggestions
+                .iter()
+                .map(|(word, info, answer)| json!({
+                    "word": self.variant.display_word(word),
+                    "info": info,
+                    "answer": answer,
+                }))
+                .collect::<Vec<_>>(),
+        }))
+    }
+    pub fn filter(&self, request: FilterRequest) -> Result<Value, HttpError> {
+        let remaining = self.remaining(&request.history)?;
+        let words: Vec<String> = self
+            .words(&remaining)
+            .iter()
+            .take(request.limit.unwrap_or(usize::MAX))
+            .map(|word| self.variant.display_word(word))
+            .collect();
+        Ok(json!({ "remaining": remaining.len(), "canditates": words }))
+    }
+    pub fn explain(&self, request: ExplainRequest) -> Result<Value, HttpError> {
+        let remaining = self.remaining(&request.history)?;
+        let word = self
+            .variant
+            .parse_word(&request.word)
+            .map_err(bad_request)?;
+        let partition = Partition::new(&word, &self.words(&remaining));
+        Ok(json!({
+            "word": self.variant.display_word(&word),
+            "info": partition.entropy(),
+            "expected_remaining": partition.expected_remaining(),
+            "worst_case": partition.worst_case(),
+            "buckets": partition
+                .buckets
+                .iter()
+                .map(|(status, words)| json!({
+                    "status": status.to_string(),
+                    "count": words.len(),
+                    "examples": words
+                        .iter()
+                        .take(5)
+                        .map(|w| self.variant.display_word(w))
+                        .collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+        }))
+    }
+    // ステータスコードとJSONの本文を返す
+    pub fn handle(&self, method: &str, path: &str, body: &str) -> (u16, String) {
+        fn parse<T: DeserializeOwned>(body: &str) -> Result<T, HttpError> {
+            serde_json::from_str(body).map_err(bad_request)
+        }
+        let result = match (method, path) {
+            ("POST", "/suggest") => parse(body).and_then(|r| self.suggest(r)),
+            ("POST", "/filter") => parse(body).and_then(|r| self.filter(r)),
+            ("POST", "/explain") => parse(body).and_then(|r| self.explain(r)),
+            (_, "/suggest" | "/filter" | "/explain") => Err((405, "use POST".to_string())),
+            _ => Err((404, format!("not found: {}", path))),
+        };
+        match result {
+            Ok(value) => (200, value.to_string()),
+            Err((status, message)) => (status, json!({ "error": message }).to_string()),
+        }
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        422 => "Unprocessable Entity",
+        _ => "Error",
+    }
+}
+
+// 一行をMAX_LINEバイトまで読む。改行までに上限を超えたらNone
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    let read = reader.take(MAX_LINE as u64 + 1).read_line(&mut line)?;
+    Ok((read <= MAX_LINE || line.ends_with('\n')).then_some(line))
+}
+
+// 一つの接続で一つのリクエストだけ受ける
+fn handle_connection(service: &Service, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let too_large = || (431, "request headers are too large".to_string());
+    let request_line = read_line(&mut reader)?;
+    let mut parts = request_line.as_deref().unwrap_or("").split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let mut length = request_line.as_ref().map(|_| 0).ok_or_else(too_large);
+    for count in 0.. {
+        let line = match read_line(&mut reader)? {
+            Some(line) if count < MAX_HEADERS => line,
+            _ => {
+                length = Err(too_large());
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                let value = value.trim();
+                length = length.and_then(|_| {
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| bad_request(format!("invalid Content-Length: {}", value)))
+                });
+            }
+        }
+    }
+    // 大きすぎる本文は確保する前に断る
+    let body = match length {
+        Ok(length) if length > MAX_BODY => {
+            Err((413, format!("body is larger than {} bytes", MAX_BODY)))
+        }
+        Ok(length) => {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            Ok(body)
+        }
+        Err(error) => Err(error),
+    };
+    // ブラウザの拡張機能から呼べるように、preflightには本文なしで答える
+    let (status, body) = match (method, body) {
+        (_, Err((status, message))) => (status, json!({ "error": message }).to_string()),
+        ("OPTIONS", Ok(_)) => (204, String::new()),
+        (_, Ok(body)) => service.handle(method, path, &String::from_utf8_lossy(&body)),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nAccess-Control-Allow-Headers: Content-Type\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// 接続ごとにスレッドを立てる。Serviceは共有する
+pub fn serve(service: Arc<Service>, listener: TcpListener) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let service = service.clone();
+        thread::spawn(move || {
+            if let Err(error) = handle_connection(&service, stream) {
+                eprintln!("error: {}", error);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        variants::{language, LoadedVariant},
+        CANDITATES,
+    };
+    use std::net::SocketAddr;
+
+    fn post(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn read_line() {
+        // 長すぎる行は最後まで読まずにNoneを返す
+        let text = format!("short\r\n{}\r\n", "a".repeat(MAX_LINE));
+        let mut reader = io::Cursor::new(text);
+        assert_eq!(super::read_line(&mut reader).unwrap().unwrap(), "short\r\n");
+        assert!(super::read_line(&mut reader).unwrap().is_none());
+        assert_eq!(reader.position() as usize, 7 + MAX_LINE + 1);
+        let exact = format!("{}\n", "a".repeat(MAX_LINE - 1));
+        let mut reader = io::Cursor::new(exact);
+        assert!(super::read_line(&mut reader).unwrap().is_some());
+    }
+
+    #[test]
+    fn service() {
+        // 表を作るのが重いので、英語の単語の一部だけを使う
+        let words: Vec<String> = CANDITATES.get_canditates()[..100]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        let words = words.join("\n");
+        let variant =
+            LoadedVariant::from_lists(language("spanish").unwrap(), &words, &words).unwrap();
+        let service = Arc::new(Service::new(Box::new(variant)));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve(service, listener));
+
+        let answer: Word = "cigar".parse().unwrap();
+        let guess = CANDITATES.get_canditates()[5];
+        let status = Word::to_status(&guess, &answer);
+        let history = format!(r#"[{{"word":"{}","status":"{}"}}]"#, guess, status);
+        let (code, filtered) = post(
+            addr,
+            "POST",
+            "/filter",
+            &format!(r#"{{"history":{}}}"#, history),
+        );
+        assert_eq!(code, 200);
+        let remaining = filtered["remaining"].as_u64().unwrap();
+        assert!((1..100).contains(&remaining));
+        assert!(filtered["canditates"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("CIGAR")));
+
+        let (code, suggested) = post(
+            addr,
+            "POST",
+            "/suggest",
+            &format!(
+                r#"{{"history":{},"tactic":"position_freq","limit":3}}"#,
+                history
+            ),
+        );
+        assert_eq!(code, 200);
+        assert_eq!(suggested["remaining"].as_u64().unwrap(), remaining);
+        assert_eq!(suggested["suggestions"].as_array().unwrap().len(), 3);
+        assert!(suggested["next"].is_string());
+
+        let (code, explained) = post(addr, "POST", "/explain", r#"{"word":"cigar"}"#);
+        assert_eq!(code, 200);
+        assert!(explained["buckets"][0]["count"].as_u64().unwrap() > 0);
+
+        let conflict = format!(
+            r#"{{"history":[{{"word":"{}","status":"{}"}},{{"word":"{}","status":"ggggg"}}]}}"#,
+            guess, status, guess
+        );
+        assert_eq!(post(addr, "POST", "/filter", &conflict).0, 422);
+        assert_eq!(post(addr, "POST", "/suggest", "not json").0, 400);
+        // 手を使い切ったhistoryには一手を返さない
+        let rows = vec![format!(r#"{{"word":"{}","status":"{}"}}"#, guess, status); 6];
+        let full = format!(r#"{{"history":[{}]}}"#, rows.join(","));
+        assert_eq!(post(addr, "POST", "/suggest", &full).0, 422);
+        assert_eq!(post(addr, "GET", "/suggest", "").0, 405);
+        assert_eq!(post(addr, "POST", "/nothing", "{}").0, 404);
+
+        // 本文の大きさは確かめてから読む
+        let send = |header: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "POST /filter HTTP/1.1\r\n{}\r\n\r\n", header).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response[9..12].parse::<u16>().unwrap()
+        };
+        assert_eq!(send("Content-Length: 99999999999"), 413);
+        assert_eq!(send("Content-Length: lots"), 400);
+
+        // avg_info_maxの一手は共有している表から選び、候補の一番目と同じ
+        let (code, suggested) = post(
+            addr,
+            "POST",
+            "/suggest",
+            &format!(r#"{{"history":{}}}"#, history),
+        );
+        assert_eq!(code, 200);
+        let words: Vec<Word> = CANDITATES.get_canditates()[..100].to_vec();
+        let mut board = avg_info_max::Board::new(words.clone(), words);
+        board.filter(&guess, &status);
+        let best = board.next();
+        assert_eq!(suggested["next"], json!(best.to_string().to_uppercase()));
+        let top = &suggested["suggestions"][0];
+        assert_eq!(top["info"].as_f64().unwrap(), board.avg_info(&best));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    enums::{Status, Word},
+    hash::Fnv1a,
+    variants::Variant,
+};
+use std::{fs, path::Path};
+
+// 単語リストが同じかどうかを見るための値。環境によって変わらないようにFNV-1aで計算する
+pub fn fingerprint(variant: &dyn Variant) -> String {
+    let mut hash = Fnv1a::new();
+    hash.feed(variant.name());
+    hash.feed("answers");
+    variant
+        .get_canditates()
+        .iter()
+        .for_each(|w| hash.feed(&w.to_string()));
+    hash.feed("guesses");
+    variant
+        .get_all_words()
+        .iter()
+        .for_each(|w| hash.feed(&w.to_string()));
+    format!(
+        "{} {} {} {:016x}",
+        variant.name(),
+        variant.get_canditates().len(),
+        variant.get_all_words().len(),
+        hash.0
+    )
+}
+
+// REPLの途中の状態。人が読んで書き換えられるように一行ずつ書く
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub tactic: String,
+    pub words: String,
+    pub hard_mode: bool,
+    pub rows: Vec<(Word, Status)>,
+}
+
+impl Session {
+    // 行の単語はvariantの表記で書き、variantで読む
+    pub fn to_text(&self, variant: &dyn Variant) -> String {
+        let mut lines = vec![
+            "# wordle-solver session".to_string(),
+            format!("tactic {}", self.tactic),
+            format!("words {}", self.words),
+            format!("hard {}", if self.hard_mode { "on" } else { "off" }),
+        ];
+        self.rows.iter().for_each(|(word, status)| {
+            lines.push(format!("row {} {}", variant.display_word(word), status))
+        });
+        lines.join("\n") + "\n"
+    }
+    pub fn parse(variant: &dyn Variant, text: &str) -> Result<Session, String> {
+        let mut tactic = None;
+        let mut words = None;
+        let mut hard_mode = false;
+        let mut rows = vec![];
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            match key {
+                "tactic" => tactic = Some(value.to_string()),
+                "words" => words = Some(value.to_string()),
+                "hard" => {
+                    hard_mode = match value {
+                        "on" => true,
+                        "off" => false,
+                        _ => return Err(format!("line {}: hard must be on or off", index + 1)),
+                    }
+                }
+                "row" => {
+                    let (word, status) = value
+                        .split_once(' ')
+                        .ok_or(format!("line {}: row needs a word and a status", index + 1))?;
+                    let word = variant
+                        .parse_word(word)
+                        .map_err(|e| format!("line {}: {}", index + 1, e))?;
+                    let status = status
+                        .trim()
+                        .parse()
+                        .map_err(|e| format!("line {}: {}", index + 1, e))?;
+                    rows.push((word, status));
+                }
+                _ => return Err(format!("line {}: unknown key {}", index + 1, key)),
+            }
+        }
+        Ok(Session {
+            tactic: tactic.ok_or("tactic is missing")?,
+            words: words.ok_or("words is missing")?,
+            hard_mode,
+            rows,
+        })
+    }
+    pub fn save(&self, variant: &dyn Variant, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_text(variant)).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+    pub fn load(variant: &dyn Variant, path: &Path) -> Result<Session, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Session::parse(variant, &text)
+    }
+    // 今の単語リストで読み込めるか
+    pub fn check_words(&self, variant: &dyn Variant) -> Result<(), String> {
+        let current = fingerprint(variant);
+        if self.words != current {
+            return Err(format!(
+                "word list does not match: saved {}, current {}",
+                self.words, current
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        variants::{language, LoadedVariant},
+        CANDITATES,
+    };
+    #[test]
+    fn round_trip() {
+        let session = Session {
+            tactic: "avg_info_max".to_string(),
+            words: fingerprint(&*CANDITATES),
+            hard_mode: true,
+            rows: vec![
+                ("soare".parse().unwrap(), "__yy_".parse().unwrap()),
+                ("cigar".parse().unwrap(), "ggggg".parse().unwrap()),
+            ],
+        };
+        let text = session.to_text(&*CANDITATES);
+        assert!(text.contains("row soare __yy_"));
+        assert_eq!(Session::parse(&*CANDITATES, &text).unwrap(), session);
+        assert!(session.check_words(&*CANDITATES).is_ok());
+        assert!(Session::parse(&*CANDITATES, "tactic x\nwords y\nhard maybe").is_err());
+        assert!(Session::parse(&*CANDITATES, "words y").is_err());
+        // 英語でない単語もvariantの表記で書いて読みもどせる
+        let german = LoadedVariant::from_lists(language("german").unwrap(), "Größe\n", "").unwrap();
+        let session = Session {
+            rows: vec![(german.get_canditates()[0], "gg___".parse().unwrap())],
+            ..session
+        };
+        let text = session.to_text(&german);
+        assert!(text.contains("row GRÖßE gg___"));
+        assert_eq!(Session::parse(&german, &text).unwrap(), session);
+        assert!(Session::parse(&*CANDITATES, &text).is_err());
+    }
+    #[test]
+    fn other_words() {
+        let english = fingerprint(&*CANDITATES);
+        let kana = LoadedVariant::from_lists(language("kana").unwrap(), "あいうえお", "あいうえお")
+            .unwrap();
+        assert_ne!(fingerprint(&kana), english);
+        let session = Session {
+            tactic: "avg_info_max".to_string(),
+            words: english,
+            hard_mode: false,
+            rows: vec![],
+        };
+        assert!(session.check_words(&kana).is_err());
+    }
+}
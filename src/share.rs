@@ -0,0 +1,178 @@
+use crate::{
+    enums::{Status, StatusChar},
+    game::{Game, GameState},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+    HighContrast,
+}
+
+impl Theme {
+    fn tile(&self, status_char: &StatusChar) -> char {
+        match (self, status_char) {
+            (Theme::HighContrast, StatusChar::Green) => '🟧',
+            (Theme::HighContrast, StatusChar::Yellow) => '🟦',
+            (_, StatusChar::Green) => '🟩',
+            (_, StatusChar::Yellow) => '🟨',
+            (Theme::Light, StatusChar::Gray) => '⬜',
+            (_, StatusChar::Gray) => '⬛',
+        }
+    }
+}
+
+// 「Wordle 245 4/6*」とその下のstatusの行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareGrid {
+    pub title: String,
+    // 解けなかったらNone
+    pub solved_in: Option<usize>,
+    pub max_guesses: usize,
+    pub hard_mode: bool,
+    pub rows: Vec<Status>,
+}
+
+impl ShareGrid {
+    pub fn from_game(title: &str, game: &Game) -> ShareGrid {
+        ShareGrid {
+            title: title.to_string(),
+            solved_in: match game.state() {
+                GameState::Won(count) => Some(count),
+                _ => None,
+            },
+            max_guesses: game.max_guesses(),
+            hard_mode: game.hard_mode(),
+            rows: game.history().iter().map(|(_, status)| *status).collect(),
+        }
+    }
+    // タイル以外の文字がある行は無視する（一行目のタイトルを除く）
+    pub fn parse(text: &str) -> Result<ShareGrid, String> {
+        let mut lines = text
+            .lines()
+            .map(|line| line.trim())
+            .filter(|l| !l.is_empty());
+        let header = lines.next().ok_or("empty share text")?;
+        let mut grid = match ShareGrid::parse_header(header) {
+            Some(grid) => grid,
+            None => ShareGrid {
+                title: String::new(),
+                solved_in: None,
+                max_guesses: 6,
+                hard_mode: false,
+                rows: vec![header.parse()?],
+            },
+        };
+        for line in lines {
+            if let Ok(status) = line.parse::<Status>() {
+                grid.rows.push(status);
+            }
+        }
+        if grid.rows.is_empty() {
+            return Err("no status rows".to_string());
+        }
+        if grid.title.is_empty() && grid.rows.last() == Some(&"ggggg".parse().unwrap()) {
+            grid.solved_in = Some(grid.rows.len());
+        }
+        Ok(grid)
+    }
+    // 何人分かのgridが続けて貼られたもの。タイトルの行で区切る
+    pub fn parse_many(text: &str) -> Result<Vec<ShareGrid>, String> {
+        let mut chunks: Vec<Vec<&str>> = vec![];
+        for line in text
+            .lines()
+            .map(|line| line.trim())
+            .filter(|l| !l.is_empty())
+        {
+            let is_row = line.parse::<Status>().is_ok();
+            match chunks.last_mut() {
+                Some(chunk) if is_row => chunk.push(line),
+                _ if !is_row && ShareGrid::parse_header(line).is_none() => {}
+                _ => chunks.push(vec![line]),
+            }
+        }
+        chunks
+            .iter()
+            .map(|chunk| ShareGrid::parse(&chunk.join("\n")))
+            .collect()
+    }
+    fn parse_header(header: &str) -> Option<ShareGrid> {
+        let score = header.split_whitespace().last()?;
+        let (score, hard_mode) = match score.strip_suffix('*') {
+            Some(score) => (score, true),
+            None => (score, false),
+        };
+        let (solved, max) = score.split_once('/')?;
+        let max_guesses = max.parse().ok()?;
+        let solved_in = match solved {
+            "X" | "x" => None,
+            solved => Some(solved.parse().ok()?),
+        };
+        let title = header[..header.rfind(score)?].trim().to_string();
+        Some(ShareGrid {
+            title,
+            solved_in,
+            max_guesses,
+            hard_mode,
+            rows: vec![],
+        })
+    }
+    pub fn render(&self, theme: Theme) -> String {
+        let score = match self.solved_in {
+            Some(count) => count.to_string(),
+            None => "X".to_string(),
+        };
+        let mut lines = vec![format!(
+            "{} {}/{}{}",
+            self.title,
+            score,
+            self.max_guesses,
+            if self.hard_mode { "*" } else { "" }
+        )
+        .trim()
+        .to_string()];
+        lines.push(String::new());
+        self.rows.iter().for_each(|status| {
+            lines.push(status.0.iter().map(|s| theme.tile(s)).collect());
+        });
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn parse_and_render() {
+        let text = "Wordle 245 3/6*\n\n⬛🟨⬛⬛🟩\n⬜🟩🟨⬜️🟩\n🟧🟧🟧🟧🟧\n";
+        let grid = ShareGrid::parse(text).unwrap();
+        assert_eq!(grid.title, "Wordle 245");
+        assert_eq!(grid.solved_in, Some(3));
+        assert!(grid.hard_mode);
+        assert_eq!(grid.rows[0].to_string(), "_y__g");
+        assert_eq!(grid.rows[1].to_string(), "_gy_g");
+        assert_eq!(grid.rows[2].to_string(), "ggggg");
+        let rendered = grid.render(Theme::Dark);
+        assert_eq!(
+            rendered,
+            "Wordle 245 3/6*\n\n⬛🟨⬛⬛🟩\n⬛🟩🟨⬛🟩\n🟩🟩🟩🟩🟩"
+        );
+        assert_eq!(ShareGrid::parse(&rendered).unwrap(), grid);
+        let failed = ShareGrid::parse("Wordle 246 X/6\n⬛⬛⬛⬛⬛").unwrap();
+        assert_eq!(failed.solved_in, None);
+        let many = ShareGrid::parse_many(&format!("{}\nnice!\n{}", text, rendered)).unwrap();
+        assert_eq!(many, vec![grid.clone(), grid]);
+    }
+    #[test]
+    fn from_game() {
+        let mut game = Game::from_index(0).unwrap();
+        game.guess(&"soare".parse().unwrap()).unwrap();
+        game.guess(&"cigar".parse().unwrap()).unwrap();
+        let grid = ShareGrid::from_game("Wordle 0", &game);
+        assert_eq!(
+            grid.render(Theme::HighContrast),
+            "Wordle 0 2/6\n\n⬛⬛🟦🟦⬛\n🟧🟧🟧🟧🟧"
+        );
+    }
+}
@@ -1,3 +1,9 @@
 pub mod avg_info_max;
+pub mod budget;
+pub mod cached;
+pub mod dynamic;
+pub mod endgame;
+pub mod lookup;
 pub mod position_freq;
 pub mod solver;
+pub mod win_prob;
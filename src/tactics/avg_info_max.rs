@@ -1,17 +1,91 @@
 use crate::enums::{Status, Word};
-use crate::get_show_console;
-use crate::tactics::solver::Solver;
-use std::{collections::HashMap, time::Instant};
-
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct WordPair(Word, Word);
-
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct WordStatusPair(Word, Status);
+use crate::patterns::{
+    bucket_counts, entropy, weighted_buckets, weighted_entropy, LiveBuckets, PatternMatrix,
+    PATTERN_COUNT,
+};
+use crate::priors::Priors;
+use crate::tactics::{budget::Budget, position_freq::quasi_infos, solver::Solver};
+use crate::{get_show_console, Stopwatch};
+use std::{
+    cell::{Ref, RefCell},
+    sync::Arc,
+};
 
 pub struct Board {
     pub remaining_canditates: Vec<Word>,
     input_canditates: Vec<Word>,
+    priors: Option<Priors>,
+    // 最初のnextで入力と候補の組のpatternを表にして、filterのたびにバケツを更新する
+    live: RefCell<Option<LiveBuckets>>,
+}
+
+impl Board {
+    fn weights(&self) -> Option<Vec<f64>> {
+        self.priors
+            .as_ref()
+            .map(|priors| priors.weights(&self.remaining_canditates))
+    }
+    // wordを入力したときに得られる平均情報量
+    pub fn avg_info(&self, word: &Word) -> f64 {
+        let weights = self.weights();
+        if let Some(live) = self.live.borrow().as_ref() {
+            if let Some(index) = live.matrix().guess_index(word) {
+                return live_info(live, index, weights.as_deref());
+            }
+        }
+        expected_info(word, &self.remaining_canditates, weights.as_deref())
+    }
+    fn live(&self) -> Ref<'_, LiveBuckets> {
+        if self.live.borrow().is_none() {
+            let start = Stopwatch::start();
+            let matrix = PatternMatrix::new(
+                self.input_canditates.clone(),
+                self.remaining_canditates.clone(),
+            );
+            *self.live.borrow_mut() = Some(LiveBuckets::new(Arc::new(matrix)));
+            if get_show_console() {
+                console!("pattern table time: {:?}", start.elapsed());
+            }
+        }
+        Ref::map(self.live.borrow(), |live| live.as_ref().unwrap())
+    }
+}
+
+// 表のindex番目の入力の平均情報量。weightsは残りの答えの順
+fn live_info(live: &LiveBuckets, index: usize, weights: Option<&[f64]>) -> f64 {
+    match weights {
+        Some(_) => matrix_info(live.matrix(), index, live.answers(), weights),
+        None => entropy(live.buckets(index)),
+    }
+}
+
+// 表のguess番目の入力でanswers（表の答えの番号）を分けたときの平均情報量。weightsはanswersの順
+pub fn matrix_info(
+    matrix: &PatternMatrix,
+    guess: usize,
+    answers: &[usize],
+    weights: Option<&[f64]>,
+) -> f64 {
+    match weights {
+        Some(weights) => {
+            let row = matrix.row(guess);
+            let mut buckets = [0f64; PATTERN_COUNT];
+            answers
+                .iter()
+                .zip(weights)
+                .for_each(|(answer, weight)| buckets[row[*answer] as usize] += weight);
+            weighted_entropy(&buckets)
+        }
+        None => entropy(&matrix.buckets(guess, answers)),
+    }
+}
+
+// remainingが答えの候補のときに、wordを入力して得られる平均情報量
+pub fn expected_info(word: &Word, remaining: &[Word], weights: Option<&[f64]>) -> f64 {
+    match weights {
+        Some(weights) => weighted_entropy(&weighted_buckets(word, remaining, weights)),
+        None => entropy(&bucket_counts(word, remaining)),
+    }
 }
 
 impl Solver for Board {
@@ -19,33 +93,43 @@ impl Solver for Board {
         Board {
             remaining_canditates: canditates,
             input_canditates: inputs,
+            priors: None,
+            live: RefCell::new(None),
         }
     }
     fn filter(&mut self, word: &Word, status: &Status) {
-        let start = Instant::now();
+        let start = Stopwatch::start();
         let &mut Board {
             ref mut remaining_canditates,
-            input_canditates: _,
+            ref mut live,
+            ..
         } = self;
         let before_len = remaining_canditates.len();
-        let remaining: Vec<Word> = remaining_canditates
-            .iter()
-            .filter(|&answer| status == &Word::to_status(word, answer))
-            .cloned()
-            .collect();
+        let remaining: Vec<Word> = match live.get_mut() {
+            Some(live) => {
+                live.filter(word, status);
+                let answers = live.matrix().answers();
+                live.answers().iter().map(|a| answers[*a]).collect()
+            }
+            None => remaining_canditates
+                .iter()
+                .filter(|&answer| status == &Word::to_status(word, answer))
+                .cloned()
+                .collect(),
+        };
         let after_len = remaining.len();
         *remaining_canditates = remaining;
         let end = start.elapsed();
         if get_show_console() {
             if remaining_canditates.len() > 2 {
-                println!(
+                console!(
                     "first three: {}, {}, {}",
-                    remaining_canditates[0].to_string(),
-                    remaining_canditates[1].to_string(),
-                    remaining_canditates[2].to_string()
+                    remaining_canditates[0],
+                    remaining_canditates[1],
+                    remaining_canditates[2]
                 );
             } else {
-                println!(
+                console!(
                     "remaining: {:?}",
                     remaining_canditates
                         .iter()
@@ -53,130 +137,110 @@ impl Solver for Board {
                         .collect::<Vec<String>>()
                 );
             }
-            println!("filter: {} -> {}", before_len, after_len);
-            println!(
+            console!("filter: {} -> {}", before_len, after_len);
+            console!(
                 "gained information: {}",
                 (before_len as f64 / after_len as f64).log2()
             );
-            println!("filter time: {:?}", end);
+            console!("filter time: {:?}", end);
         }
     }
     fn next(&self) -> Word {
         if self.remaining_canditates.len() <= 2 {
-            return self.remaining_canditates[0];
-        }
-        let all_start = Instant::now();
-        let start = Instant::now();
-        let &Board {
-            ref remaining_canditates,
-            ref input_canditates,
-        } = self;
-        // construct board
-        let mut status_board = HashMap::new();
-        let input_len = input_canditates.len() as u32;
-        let mut current = 0f64;
-        input_canditates
-            .iter()
-            .enumerate()
-            .for_each(|(i_index, word)| {
-                remaining_canditates
-                    .iter()
-                    .enumerate()
-                    .for_each(|(_a_index, answer)| {
-                        let status = Word::to_status(word, answer);
-                        status_board.insert(WordPair(*word, *answer), status);
-                    });
-                if get_show_console() {
-                    let percentage = (i_index as f64 / input_len as f64) * 100.0;
-                    let percentage = ((percentage / 10.0).floor() as i64 * 10) as f64;
-                    if percentage >= (current + 10.0) {
-                        current = (current + 10.0).max(percentage);
-                        println!("board percentage {}%", current);
-                    }
-                }
-            });
-        let end = start.elapsed();
-        if get_show_console() {
-            println!("board time: {:?}", end);
+            return match &self.priors {
+                Some(priors) => priors.posterior(&self.remaining_canditates)[0].0,
+                None => self.remaining_canditates[0],
+            };
         }
-        // construct color groping and word_avg_info
-        let start = Instant::now();
-        let len_of_remaining_canditates = remaining_canditates.len() as u32;
-        let input_len = input_canditates.len() as u32;
-        let mut current = 0f64;
-        let mut word_color_grouping = HashMap::new();
-        let mut word_avg_info = HashMap::new();
-        input_canditates
+        let all_start = Stopwatch::start();
+        let input_canditates = &self.input_canditates;
+        let weights = self.weights();
+        let live = self.live();
+        // 表のバケツから平均情報量を出す
+        let start = Stopwatch::start();
+        let word_avg_info: Vec<(Word, f64)> = input_canditates
             .iter()
             .enumerate()
-            .for_each(|(i_index, word)| {
-                remaining_canditates.iter().for_each(|answer| {
-                    let status = status_board.get(&WordPair(*word, *answer)).unwrap();
-                    word_color_grouping.insert(
-                        WordStatusPair(*word, *status),
-                        *word_color_grouping
-                            .get(&WordStatusPair(*word, *status))
-                            .unwrap_or(&0)
-                            + 1,
-                    );
-                });
-                let mut avg_info = 0f64;
-                Status::get_status_iter().for_each(|status| {
-                    let count = *word_color_grouping
-                        .get(&WordStatusPair(*word, status))
-                        .unwrap_or(&0);
-                    if count == 0 {
-                        return;
-                    }
-                    let probablity = count as f64 / len_of_remaining_canditates as f64;
-                    avg_info += -probablity * probablity.log2();
-                });
-                word_avg_info.insert(*word, avg_info);
-                if get_show_console() {
-                    let percentage = (i_index as f64 / input_len as f64) * 100.0;
-                    let percentage = ((percentage / 10.0).floor() as i64 * 10) as f64;
-                    if percentage >= (current + 10.0) {
-                        current = (current + 10.0).max(percentage);
-                        println!("info percentage {}%", current);
-                    }
-                }
-            });
+            .map(|(index, word)| (*word, live_info(&live, index, weights.as_deref())))
+            .collect();
         if get_show_console() {
             let end = start.elapsed();
-            println!("info time: {:?}", end);
+            console!("info time: {:?}", end);
         }
         // search next word
-        let start = Instant::now();
+        let start = Stopwatch::start();
         let (word, info) = word_avg_info
             .iter()
+            .rev()
             .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
             .unwrap();
         if get_show_console() {
             let end = start.elapsed();
             let all_end = all_start.elapsed();
-            println!("search time: {:?}", end);
-            println!("next word time: {:?}", all_end);
-            println!("next word: {}", word.to_string());
-            println!("next word info: {}", info);
+            console!("search time: {:?}", end);
+            console!("next word time: {:?}", all_end);
+            console!("next word: {}", word);
+            console!("next word info: {}", info);
         }
         *word
     }
+    // 位置ごとの出現回数で見積もった順に調べて、budgetが尽きたらやめる
+    fn next_with_budget(&self, budget: &Budget) -> (Word, bool) {
+        if self.remaining_canditates.len() <= 2 {
+            return (self.next(), true);
+        }
+        let weights = self.weights();
+        // 表がまだなければ作る。filterのたびに更新されるので、次の手からは数えなおさない
+        let live = self.live();
+        let mut order: Vec<(usize, (Word, f64))> =
+            quasi_infos(&self.remaining_canditates, &self.input_canditates)
+                .into_iter()
+                .enumerate()
+                .collect();
+        order.sort_by(|a, b| b.1 .1.partial_cmp(&a.1 .1).unwrap());
+        let mut best: Option<(Word, f64)> = None;
+        for (index, (input, (word, _))) in order.iter().enumerate() {
+            // 最初の一つは必ず調べる
+            if index > 0 && index % 64 == 0 && budget.is_over() {
+                if get_show_console() {
+                    console!("searched {} / {} words", index, order.len());
+                }
+                return (best.unwrap().0, false);
+            }
+            let info = live_info(&live, *input, weights.as_deref());
+            if best.is_none_or(|(_, max)| info > max) {
+                best = Some((*word, info));
+            }
+        }
+        (best.unwrap().0, true)
+    }
+    fn name(&self) -> String {
+        "avg_info_max".to_string()
+    }
+    fn remaining(&self) -> &[Word] {
+        &self.remaining_canditates
+    }
+    fn with_priors(mut self, priors: Priors) -> Board {
+        self.priors = Some(priors);
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::set_show_console;
+    use std::collections::HashMap;
 
     use super::*;
     #[test]
     fn filter() {
         set_show_console(true);
         let mut board = Board::new(
-            vec!["abcde", "fghij", "klmno", "pqrst"]
+            ["abcde", "fghij", "klmno", "pqrst"]
                 .iter()
                 .map(|s| s.parse().unwrap())
                 .collect(),
-            vec!["afkpz", "fghij", "klmno", "pqrst"]
+            ["afkpz", "fghij", "klmno", "pqrst"]
                 .iter()
                 .map(|s| s.parse().unwrap())
                 .collect(),
@@ -188,11 +252,11 @@ mod tests {
     fn info() {
         set_show_console(true);
         let board = Board::new(
-            vec!["abcde", "fghij", "klmno", "pqrst"]
+            ["abcde", "fghij", "klmno", "pqrst"]
                 .iter()
                 .map(|s| s.parse().unwrap())
                 .collect(),
-            vec!["afkpz", "fghij", "klmno", "pqrst"]
+            ["afkpz", "fghij", "klmno", "pqrst"]
                 .iter()
                 .map(|s| s.parse().unwrap())
                 .collect(),
@@ -200,13 +264,57 @@ mod tests {
         let next = board.next();
         assert_eq!(next, "afkpz".parse().unwrap());
     }
+    #[test]
+    fn weighted_info() {
+        let words: Vec<Word> = ["abcde", "abcdf", "abcgh", "ijklm"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let board = Board::new(words.clone(), words.clone());
+        // 一つの単語がほとんど答えなら、どの単語を入れても情報量は小さい
+        let mut weights = HashMap::new();
+        weights.insert(words[3], 1000.0);
+        let weighted =
+            Board::new(words.clone(), words.clone()).with_priors(Priors::new(weights, 1.0));
+        assert!(weighted.avg_info(&words[0]) < board.avg_info(&words[0]));
+        assert!((board.avg_info(&words[0]) - 2.0).abs() < 1e-9);
+    }
+    #[test]
+    fn incremental() {
+        let canditates = crate::CANDITATES.get_canditates()[..300].to_vec();
+        let inputs = crate::CANDITATES.get_all_words()[..1500].to_vec();
+        // 先にnextを呼んだboardは表を更新していく
+        let mut live = Board::new(canditates.clone(), inputs.clone());
+        live.next();
+        let mut fresh = Board::new(canditates.clone(), inputs.clone());
+        for (guess, answer) in [
+            (inputs[100], canditates[7]),
+            (canditates[30], canditates[7]),
+        ] {
+            let status = Word::to_status(&guess, &answer);
+            live.filter(&guess, &status);
+            fresh.filter(&guess, &status);
+            assert_eq!(live.remaining(), fresh.remaining());
+            assert!(live.live.borrow().is_some());
+            let next = live.next();
+            assert!((live.avg_info(&next) - fresh.avg_info(&fresh.next())).abs() < 1e-9);
+            assert!((live.avg_info(&inputs[5]) - fresh.avg_info(&inputs[5])).abs() < 1e-9);
+            fresh = Board::new(fresh.remaining().to_vec(), inputs.clone());
+        }
+        // 時間を区切った一手でも表を作る
+        let board = Board::new(canditates, inputs);
+        board.next_with_budget(&Budget::unlimited());
+        assert!(board.live.borrow().is_some());
+    }
 }
 
 #[cfg(test)]
 mod bench {
     use super::*;
-    use crate::enums::Word;
-    use crate::CANDITATES;
+    use crate::tactics::cached::{Cached, RecommendationCache};
+    use crate::tournament::Tournament;
+    use crate::{set_show_console, CANDITATES};
+    use std::path::PathBuf;
     #[test]
     #[ignore]
     fn get_maximum() {
@@ -216,36 +324,30 @@ mod bench {
     #[test]
     #[ignore]
     fn get_avg_count() {
-        let best_first = "soare";
-        let all_answers = CANDITATES.get_canditates();
-        let mut average_count = 0;
-        let answer_len = all_answers.len();
-        let mut current = 0f64;
-        all_answers.iter().enumerate().for_each(|(a_idx, answer)| {
-            let mut board = Board::new(CANDITATES.get_canditates(), CANDITATES.get_all_words());
-            let first_word: Word = best_first.parse().unwrap();
-            let first_status = Word::to_status(&first_word, answer);
-            board.filter(&first_word, &first_status);
-            average_count += 1;
-            loop {
-                if board.remaining_canditates.len() == 1 && board.remaining_canditates[0] == *answer
-                {
-                    average_count += 1;
-                    break;
-                }
-                let next_word = board.next();
-                let status = Word::to_status(&next_word, answer);
-                board.filter(&next_word, &status);
-                average_count += 1;
-            }
-            let percentage = (a_idx as f64 / answer_len as f64) * 100.0;
-            let percentage = ((percentage / 5.0).floor() as i64 * 5) as f64;
-            if percentage >= (current + 5.0) {
-                current = (current + 5.0).max(percentage);
-                println!("board percentage {}%", current);
-            }
+        // WORDLE_CACHEにファイルを指定すると、前の実行で覚えた一手を使い回す
+        let cache_path = std::env::var("WORDLE_CACHE").ok().map(PathBuf::from);
+        let cache = match &cache_path {
+            Some(path) => RecommendationCache::load(path).unwrap(),
+            None => RecommendationCache::new(),
+        }
+        .shared();
+        set_show_console(true);
+        let mut tournament =
+            Tournament::new(CANDITATES.get_canditates(), CANDITATES.get_all_words())
+                .with_opener(Some("soare".parse().unwrap()))
+                .with_max_guesses(usize::MAX);
+        let entry = tournament.run_with(|| {
+            Cached::<Board>::new(CANDITATES.get_canditates(), CANDITATES.get_all_words())
+                .with_cache(cache.clone())
         });
-        let average_count = average_count as f64 / all_answers.len() as f64;
-        println!("average count: {}", average_count);
+        if std::env::var("SHOW_GRIDS").is_ok() {
+            entry.grids().iter().for_each(|grid| println!("{}", grid));
+        }
+        let cache = cache.lock().unwrap();
+        println!("cache hits: {}, misses: {}", cache.hits, cache.misses);
+        if let Some(path) = cache_path {
+            cache.save(&path).unwrap();
+        }
+        println!("average count: {}", tournament.leaderboard()[0].mean);
     }
 }
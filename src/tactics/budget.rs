@@ -0,0 +1,185 @@
+use crate::{
+    enums::{Status, Word},
+    priors::Priors,
+    tactics::solver::Solver,
+    Stopwatch,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+// ほかのスレッドから探索を止めるためのもの。cloneしたものは同じフラグを共有する
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// 一手にかけてよい時間。作ったときから測る
+pub struct Budget {
+    start: Stopwatch,
+    limit: Option<Duration>,
+    token: Option<CancelToken>,
+}
+
+impl Budget {
+    pub fn unlimited() -> Budget {
+        Budget {
+            start: Stopwatch::start(),
+            limit: None,
+            token: None,
+        }
+    }
+    pub fn with_time_limit(limit: Duration) -> Budget {
+        Budget {
+            limit: Some(limit),
+            ..Budget::unlimited()
+        }
+    }
+    pub fn with_cancel(mut self, token: CancelToken) -> Budget {
+        self.token = Some(token);
+        self
+    }
+    pub fn is_over(&self) -> bool {
+        self.token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+            || self
+                .limit
+                .is_some_and(|limit| self.start.elapsed() >= limit)
+    }
+}
+
+// 一手ごとに時間を区切るsolver。大会用のバイナリで使う
+pub struct TimeLimited<S: Solver> {
+    inner: S,
+    limit: Option<Duration>,
+}
+
+impl<S: Solver> TimeLimited<S> {
+    pub fn with_time_limit(mut self, limit: Option<Duration>) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<S: Solver> Solver for TimeLimited<S> {
+    fn new(canditates: Vec<Word>, inputs: Vec<Word>) -> Self {
+        TimeLimited {
+            inner: S::new(canditates, inputs),
+            limit: None,
+        }
+    }
+    fn filter(&mut self, word: &Word, status: &Status) {
+        self.inner.filter(word, status);
+    }
+    fn next(&self) -> Word {
+        match self.limit {
+            Some(limit) => {
+                self.inner
+                    .next_with_budget(&Budget::with_time_limit(limit))
+                    .0
+            }
+            None => self.inner.next(),
+        }
+    }
+    // 手数を気にする戦略を包んでも、残りの手数をそのまま渡す
+    fn next_within(&self, turns: usize) -> Word {
+        match self.limit {
+            Some(limit) => {
+                self.inner
+                    .next_within_budget(turns, &Budget::with_time_limit(limit))
+                    .0
+            }
+            None => self.inner.next_within(turns),
+        }
+    }
+    fn next_with_budget(&self, budget: &Budget) -> (Word, bool) {
+        self.inner.next_with_budget(budget)
+    }
+    fn next_within_budget(&self, turns: usize, budget: &Budget) -> (Word, bool) {
+        self.inner.next_within_budget(turns, budget)
+    }
+    fn turns_left(&self) -> Option<usize> {
+        self.inner.turns_left()
+    }
+    fn opener(&self) -> Option<Word> {
+        self.inner.opener()
+    }
+    fn remaining(&self) -> &[Word] {
+        self.inner.remaining()
+    }
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+    fn with_priors(mut self, priors: Priors) -> Self {
+        self.inner = self.inner.with_priors(priors);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tactics::{avg_info_max, win_prob},
+        CANDITATES,
+    };
+    #[test]
+    fn budget() {
+        let board = avg_info_max::Board::new(
+            CANDITATES.get_canditates()[..30].to_vec(),
+            CANDITATES.get_all_words(),
+        );
+        let (word, completed) = board.next_with_budget(&Budget::unlimited());
+        assert!(completed);
+        assert!((board.avg_info(&word) - board.avg_info(&board.next())).abs() < 1e-9);
+        // 止められていても、位置ごとの出現回数で一番よい単語は調べる
+        let token = CancelToken::new();
+        token.cancel();
+        let (first, completed) =
+            board.next_with_budget(&Budget::unlimited().with_cancel(token.clone()));
+        assert!(!completed);
+        assert!(board.avg_info(&first) <= board.avg_info(&word));
+        let (_, completed) = board.next_with_budget(&Budget::with_time_limit(Duration::ZERO));
+        assert!(!completed);
+
+        let limited = TimeLimited::<avg_info_max::Board>::new(
+            CANDITATES.get_canditates()[..30].to_vec(),
+            CANDITATES.get_all_words(),
+        )
+        .with_time_limit(Some(Duration::ZERO));
+        assert_eq!(limited.next(), first);
+        assert_eq!(limited.next_within(3), first);
+
+        // 手数を気にする戦略は、時間を区切っても残りの手数を受け取る。
+        // 止められていても一つ目の候補は調べ、flmnsなら二手で必ず当てられるのでそこで終わる
+        let remaining: Vec<Word> = ["fight", "light", "might", "night", "sight", "tight"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let mut inputs = remaining.clone();
+        inputs.push("flmns".parse().unwrap());
+        let limited = TimeLimited::<win_prob::Board>::new(remaining.clone(), inputs)
+            .with_time_limit(Some(Duration::ZERO));
+        assert_eq!(
+            limited.next_within_budget(2, &Budget::with_time_limit(Duration::ZERO)),
+            ("flmns".parse().unwrap(), true)
+        );
+        assert_eq!(limited.next_within(2), "flmns".parse().unwrap());
+        assert!(remaining.contains(&limited.next_within(1)));
+    }
+}
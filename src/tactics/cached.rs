@@ -0,0 +1,262 @@
+use crate::{
+    enums::{Status, Word},
+    hash::Fnv1a,
+    priors::Priors,
+    tactics::{budget::Budget, solver::Solver},
+};
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+// 残りの候補の集合と戦略の設定から、次の一手を引く表
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecommendationCache {
+    entries: HashMap<u64, Word>,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+// 何局ものboardで共有する
+pub type SharedCache = Arc<Mutex<RecommendationCache>>;
+
+impl RecommendationCache {
+    pub fn new() -> RecommendationCache {
+        RecommendationCache::default()
+    }
+    pub fn shared(self) -> SharedCache {
+        Arc::new(Mutex::new(self))
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn get(&mut self, key: u64) -> Option<Word> {
+        let word = self.entries.get(&key).copied();
+        match word {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        word
+    }
+    pub fn insert(&mut self, key: u64, word: Word) {
+        self.entries.insert(key, word);
+    }
+    // 一行に「key 単語」。keyの順に並べる
+    pub fn to_text(&self) -> String {
+        let mut lines = vec!["# wordle-solver cache".to_string()];
+        let mut entries: Vec<(&u64, &Word)> = self.entries.iter().collect();
+        entries.sort_by_key(|(key, _)| **key);
+        entries
+            .iter()
+            .for_each(|(key, word)| lines.push(format!("{:016x} {}", key, word)));
+        lines.join("\n") + "\n"
+    }
+    pub fn parse(text: &str) -> Result<RecommendationCache, String> {
+        let mut cache = RecommendationCache::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+            let (key, word) = line
+                .split_once(' ')
+                .ok_or_else(|| error(format!("expected <key> <word>: {}", line)))?;
+            let key = u64::from_str_radix(key, 16).map_err(|e| error(e.to_string()))?;
+            cache.insert(key, word.trim().parse().map_err(error)?);
+        }
+        Ok(cache)
+    }
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_text()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+    // ファイルがなければ空の表
+    pub fn load(path: &Path) -> Result<RecommendationCache, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => RecommendationCache::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(RecommendationCache::new()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+}
+
+// Sのnextを表に覚えておく。同じ候補の集合なら二回目からは計算しない
+pub struct Cached<S: Solver> {
+    inner: S,
+    // 戦略の名前、入力できる単語、答えの重みのハッシュ
+    config: Fnv1a,
+    cache: SharedCache,
+}
+
+impl<S: Solver> Cached<S> {
+    pub fn with_cache(mut self, cache: SharedCache) -> Self {
+        self.cache = cache;
+        self
+    }
+    pub fn cache(&self) -> &SharedCache {
+        &self.cache
+    }
+    fn key(&self, turns: Option<usize>) -> u64 {
+        let mut hash = Fnv1a(self.config.0);
+        let mut remaining = self.inner.remaining().to_vec();
+        remaining.sort_by_key(|word| word.0);
+        remaining
+            .iter()
+            .for_each(|word| hash.feed(&word.to_string()));
+        if let Some(turns) = turns {
+            hash.feed(&format!("turns {}", turns));
+        }
+        hash.0
+    }
+    // 途中で打ち切られた一手は覚えない
+    fn cached_with_budget(
+        &self,
+        turns: Option<usize>,
+        next: impl FnOnce() -> (Word, bool),
+    ) -> (Word, bool) {
+        let key = self.key(turns);
+        if let Some(word) = self.cache.lock().unwrap().get(key) {
+            return (word, true);
+        }
+        let (word, completed) = next();
+        if completed {
+            self.cache.lock().unwrap().insert(key, word);
+        }
+        (word, completed)
+    }
+    fn cached(&self, turns: Option<usize>, next: impl FnOnce() -> Word) -> Word {
+        let key = self.key(turns);
+        if let Some(word) = self.cache.lock().unwrap().get(key) {
+            return word;
+        }
+        // 計算している間は表をロックしない
+        let word = next();
+        self.cache.lock().unwrap().insert(key, word);
+        word
+    }
+}
+
+impl<S: Solver> Solver for Cached<S> {
+    fn new(canditates: Vec<Word>, inputs: Vec<Word>) -> Self {
+        let inner = S::new(canditates, inputs.clone());
+        let mut config = Fnv1a::new();
+        config.feed(&inner.name());
+        inputs
+            .iter()
+            .for_each(|word| config.feed(&word.to_string()));
+        Cached {
+            inner,
+            config,
+            cache: RecommendationCache::new().shared(),
+        }
+    }
+    fn filter(&mut self, word: &Word, status: &Status) {
+        self.inner.filter(word, status);
+    }
+    // 手数によって一手が変わる戦略なら、残りの手数も鍵に入れる
+    fn next(&self) -> Word {
+        self.cached(self.inner.turns_left(), || self.inner.next())
+    }
+    fn next_within(&self, turns: usize) -> Word {
+        self.cached(Some(turns), || self.inner.next_within(turns))
+    }
+    fn next_with_budget(&self, budget: &Budget) -> (Word, bool) {
+        self.cached_with_budget(self.inner.turns_left(), || {
+            self.inner.next_with_budget(budget)
+        })
+    }
+    fn next_within_budget(&self, turns: usize, budget: &Budget) -> (Word, bool) {
+        self.cached_with_budget(Some(turns), || self.inner.next_within_budget(turns, budget))
+    }
+    fn turns_left(&self) -> Option<usize> {
+        self.inner.turns_left()
+    }
+    fn opener(&self) -> Option<Word> {
+        self.inner.opener()
+    }
+    fn remaining(&self) -> &[Word] {
+        self.inner.remaining()
+    }
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+    fn with_priors(mut self, priors: Priors) -> Self {
+        // 答えの候補の重み
+        let mut canditates = self.inner.remaining().to_vec();
+        canditates.sort_by_key(|word| word.0);
+        self.config.feed("priors");
+        priors
+            .weights(&canditates)
+            .iter()
+            .for_each(|weight| self.config.feed(&format!("{:e}", weight)));
+        self.inner = self.inner.with_priors(priors);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tactics::{avg_info_max, position_freq, win_prob};
+    use crate::CANDITATES;
+    #[test]
+    fn cache() {
+        let canditates = CANDITATES.get_canditates()[..100].to_vec();
+        let inputs = CANDITATES.get_all_words()[..500].to_vec();
+        let cache = RecommendationCache::new().shared();
+        let guess = inputs[0];
+        let status = Word::to_status(&guess, &canditates[0]);
+        let play = |cache: &SharedCache| {
+            let mut board = Cached::<position_freq::Board>::new(canditates.clone(), inputs.clone())
+                .with_cache(cache.clone());
+            board.filter(&guess, &status);
+            board.next()
+        };
+        let first = play(&cache);
+        assert_eq!(play(&cache), first);
+        assert_eq!(cache.lock().unwrap().hits, 1);
+        assert_eq!(cache.lock().unwrap().misses, 1);
+        // 戦略や手数が違えば別のkey
+        let board = Cached::<avg_info_max::Board>::new(canditates.clone(), inputs.clone())
+            .with_cache(cache.clone());
+        board.next();
+        board.next_within(3);
+        assert_eq!(cache.lock().unwrap().len(), 3);
+
+        let path = std::env::temp_dir().join(format!("wordle-cache-{}.txt", std::process::id()));
+        cache.lock().unwrap().save(&path).unwrap();
+        let loaded = RecommendationCache::load(&path).unwrap().shared();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(play(&loaded), first);
+        assert_eq!(loaded.lock().unwrap().hits, 1);
+        assert!(RecommendationCache::load(&path).unwrap().is_empty());
+        assert!(RecommendationCache::parse("0123 qqqqqq").is_err());
+    }
+    #[test]
+    fn turns() {
+        let remaining: Vec<Word> = ["fight", "light", "might", "night", "sight", "tight"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let mut inputs = remaining.clone();
+        inputs.push("flmns".parse().unwrap());
+        let cache = RecommendationCache::new().shared();
+        let board = |filters: usize| {
+            let mut board = Cached::<win_prob::Board>::new(remaining.clone(), inputs.clone())
+                .with_cache(cache.clone());
+            // 候補を変えずに手数だけ使う
+            (0..filters)
+                .for_each(|_| board.filter(&"zzzzz".parse().unwrap(), &"_____".parse().unwrap()));
+            board
+        };
+        // 手数が残っていれば分ける単語、最後の一手なら候補から選ぶ
+        assert_eq!(board(0).next(), "flmns".parse().unwrap());
+        assert!(remaining.contains(&board(5).next()));
+        assert_eq!(cache.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    enums::{Status, Word},
+    priors::Priors,
+    tactics::{avg_info_max, position_freq, solver::Solver, win_prob},
+    validation::{check_filter, FeedbackError},
+    variants::Variant,
+};
+
+enum Tactic {
+    AvgInfoMax(avg_info_max::Board),
+    PositionFreq(position_freq::Board),
+    WinProb(win_prob::Board),
+}
+
+// 戦略を名前で選んで、実行時に切り替えられるboard。wasmやC APIから使う
+pub struct DynBoard {
+    name: String,
+    tactic: Tactic,
+    canditates: Vec<Word>,
+    dictionary: Vec<Word>,
+    priors: Option<Priors>,
+    history: Vec<(Word, Status)>,
+}
+
+impl DynBoard {
+    pub fn new(name: &str, variant: &dyn Variant) -> Result<DynBoard, String> {
+        let mut board = DynBoard {
+            name: name.to_string(),
+            tactic: Tactic::PositionFreq(position_freq::Board::new(vec![], vec![])),
+            canditates: variant.get_canditates(),
+            dictionary: variant.get_all_words(),
+            priors: variant.priors(),
+            history: vec![],
+        };
+        board.tactic = board.fresh()?;
+        Ok(board)
+    }
+    fn fresh(&self) -> Result<Tactic, String> {
+        fn board<S: Solver>(board: &DynBoard) -> S {
+            let solver = S::new(board.canditates.clone(), board.dictionary.clone());
+            match &board.priors {
+                Some(priors) => solver.with_priors(priors.clone()),
+                None => solver,
+            }
+        }
+        match self.name.as_str() {
+            "avg_info_max" => Ok(Tactic::AvgInfoMax(board(self))),
+            "position_freq" => Ok(Tactic::PositionFreq(board(self))),
+            "win_prob" => Ok(Tactic::WinProb(board(self))),
+            _ => Err(format!("unknown tactic: {}", self.name)),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn dictionary(&self) -> &[Word] {
+        &self.dictionary
+    }
+    pub fn history(&self) -> &[(Word, Status)] {
+        &self.history
+    }
+    pub fn remaining(&self) -> &[Word] {
+        match &self.tactic {
+            Tactic::AvgInfoMax(board) => board.remaining(),
+            Tactic::PositionFreq(board) => board.remaining(),
+            Tactic::WinProb(board) => board.remaining(),
+        }
+    }
+    // 矛盾する行は入れない。残りの候補の数を返す
+    pub fn apply(&mut self, word: &Word, status: &Status) -> Result<usize, FeedbackError> {
+        check_filter(
+            &self.history,
+            self.remaining(),
+            &self.dictionary,
+            word,
+            status,
+        )?;
+        match &mut self.tactic {
+            Tactic::AvgInfoMax(board) => board.filter(word, status),
+            Tactic::PositionFreq(board) => board.filter(word, status),
+            Tactic::WinProb(board) => board.filter(word, status),
+        }
+        self.history.push((*word, *status));
+        Ok(self.remaining().len())
+    }
+    pub fn next(&self) -> Option<Word> {
+        if self.remaining().is_empty() {
+            return None;
+        }
+        Some(match &self.tactic {
+            Tactic::AvgInfoMax(board) => board.next(),
+            Tactic::PositionFreq(board) => board.next(),
+            Tactic::WinProb(board) => board.next(),
+        })
+    }
+    pub fn reset(&mut self) {
+        self.tactic = self.fresh().unwrap();
+        self.history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CANDITATES;
+    #[test]
+    fn by_name() {
+        let mut board = DynBoard::new("position_freq", &*CANDITATES).unwrap();
+        let status = "__yy_".parse().unwrap();
+        assert_eq!(board.apply(&"soare".parse().unwrap(), &status), Ok(42));
+        assert!(board
+            .apply(&"cigar".parse().unwrap(), &"ggggy".parse().unwrap())
+            .is_err());
+        assert_eq!(board.history().len(), 1);
+        assert!(board.next().is_some());
+        board.reset();
+        assert_eq!(board.remaining().len(), CANDITATES.get_canditates().len());
+        assert!(DynBoard::new("nothing", &*CANDITATES).is_err());
+    }
+}
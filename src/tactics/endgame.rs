@@ -0,0 +1,284 @@
+use crate::{
+    enums::{Status, Word},
+    patterns::{bucket_counts, pattern_id, PatternId, ALL_GREEN, PATTERN_COUNT},
+    priors::Priors,
+    tactics::{budget::Budget, solver::Solver},
+};
+use std::{cell::RefCell, collections::HashMap};
+
+pub const DEFAULT_THRESHOLD: usize = 20;
+pub const DEFAULT_PROBES: usize = 20;
+
+// 候補が少なくなったら、当てるまでの手数の期待値が最小になる一手を全部調べて選ぶ
+pub struct EndgameSearch<'a> {
+    inputs: &'a [Word],
+    // 候補の外から調べる単語の数
+    probes: usize,
+    memo: HashMap<(Vec<Word>, usize), Option<f64>>,
+}
+
+impl<'a> EndgameSearch<'a> {
+    pub fn new(inputs: &'a [Word], probes: usize) -> EndgameSearch<'a> {
+        EndgameSearch {
+            inputs,
+            probes,
+            memo: HashMap::new(),
+        }
+    }
+    // 一手目を含めた手数の期待値が最小になる単語。turns手以内に必ず当てられないならNone
+    pub fn best(&mut self, remaining: &[Word], turns: usize) -> Option<(Word, f64)> {
+        if remaining.len() == 1 {
+            return (turns >= 1).then_some((remaining[0], 1.0));
+        }
+        let mut best: Option<(Word, f64)> = None;
+        for guess in self.guesses(remaining) {
+            let bound = best.map_or(f64::INFINITY, |(_, cost)| cost);
+            if let Some(cost) = self.cost_of(&guess, remaining, turns, bound) {
+                if cost < bound {
+                    best = Some((guess, cost));
+                }
+            }
+        }
+        best
+    }
+    // 候補の単語と、候補をよく分ける候補の外の単語
+    fn guesses(&self, remaining: &[Word]) -> Vec<Word> {
+        let mut guesses = remaining.to_vec();
+        if remaining.len() <= 2 {
+            return guesses;
+        }
+        let mut probes: Vec<(Word, usize, usize)> = self
+            .inputs
+            .iter()
+            .filter(|word| !remaining.contains(word))
+            .map(|word| {
+                let buckets = bucket_counts(word, remaining);
+                let count = buckets.iter().filter(|c| **c > 0).count();
+                (*word, count, *buckets.iter().max().unwrap() as usize)
+            })
+            .filter(|(_, count, _)| *count > 1)
+            .collect();
+        probes.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.cmp(&b.2)));
+        guesses.extend(probes.iter().take(self.probes).map(|(word, _, _)| *word));
+        guesses
+    }
+    // boundより悪くなるとわかったら途中でやめてNone
+    fn cost_of(
+        &mut self,
+        guess: &Word,
+        remaining: &[Word],
+        turns: usize,
+        bound: f64,
+    ) -> Option<f64> {
+        if turns == 0 {
+            return None;
+        }
+        let n = remaining.len() as f64;
+        let buckets = split(guess, remaining);
+        // 何もわからない一手は調べない
+        if buckets.len() == 1 && !remaining.contains(guess) {
+            return None;
+        }
+        let mut cost = 1.0;
+        // まだ調べていないバケツの下限（二つ以上なら少なくとも(2m-1)/m手）
+        let mut rest: f64 = buckets
+            .iter()
+            .map(|(id, words)| match *id == ALL_GREEN {
+                true => 0.0,
+                false => lower_bound(words.len()) * words.len() as f64 / n,
+            })
+            .sum();
+        for (id, words) in buckets.iter() {
+            if *id == ALL_GREEN {
+                continue;
+            }
+            let weight = words.len() as f64 / n;
+            rest -= lower_bound(words.len()) * weight;
+            if cost + rest + lower_bound(words.len()) * weight >= bound {
+                return None;
+            }
+            cost += self.cost(words, turns - 1)? * weight;
+        }
+        Some(cost)
+    }
+    fn cost(&mut self, remaining: &[Word], turns: usize) -> Option<f64> {
+        let key = (remaining.to_vec(), turns);
+        if let Some(cost) = self.memo.get(&key) {
+            return *cost;
+        }
+        let cost = match remaining.len() {
+            1 if turns >= 1 => Some(1.0),
+            2 if turns >= 2 => Some(1.5),
+            _ => self.best(remaining, turns).map(|(_, cost)| cost),
+        };
+        self.memo.insert(key, cost);
+        cost
+    }
+}
+
+fn lower_bound(size: usize) -> f64 {
+    (2 * size - 1) as f64 / size as f64
+}
+
+fn split(guess: &Word, remaining: &[Word]) -> Vec<(PatternId, Vec<Word>)> {
+    let mut groups: Vec<Vec<Word>> = vec![vec![]; PATTERN_COUNT];
+    remaining
+        .iter()
+        .for_each(|answer| groups[pattern_id(guess, answer) as usize].push(*answer));
+    groups
+        .into_iter()
+        .enumerate()
+        .filter(|(_, words)| !words.is_empty())
+        .map(|(id, words)| (id as PatternId, words))
+        .collect()
+}
+
+// 候補がthreshold以下になったら終盤の探索に任せる。それまではSの一手
+pub struct Endgame<S: Solver> {
+    inner: S,
+    inputs: Vec<Word>,
+    threshold: usize,
+    probes: usize,
+    max_guesses: usize,
+    guesses: usize,
+    // 同じ候補で何度もnextを呼ばれたときのため
+    cache: RefCell<Option<(Vec<Word>, usize, Word)>>,
+}
+
+impl<S: Solver> Endgame<S> {
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+    pub fn with_probes(mut self, probes: usize) -> Self {
+        self.probes = probes;
+        self
+    }
+    pub fn with_max_guesses(mut self, max_guesses: usize) -> Self {
+        self.max_guesses = max_guesses;
+        self
+    }
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: Solver> Solver for Endgame<S> {
+    fn new(canditates: Vec<Word>, inputs: Vec<Word>) -> Self {
+        Endgame {
+            inner: S::new(canditates, inputs.clone()),
+            inputs,
+            threshold: DEFAULT_THRESHOLD,
+            probes: DEFAULT_PROBES,
+            max_guesses: 6,
+            guesses: 0,
+            cache: RefCell::new(None),
+        }
+    }
+    fn filter(&mut self, word: &Word, status: &Status) {
+        self.inner.filter(word, status);
+        self.guesses += 1;
+    }
+    fn next(&self) -> Word {
+        self.next_within(self.max_guesses.saturating_sub(self.guesses))
+    }
+    fn next_within(&self, turns: usize) -> Word {
+        let remaining = self.inner.remaining();
+        if remaining.is_empty() || remaining.len() > self.threshold {
+            return self.inner.next_within(turns);
+        }
+        if let Some((cached, cached_turns, word)) = self.cache.borrow().as_ref() {
+            if cached == remaining && *cached_turns == turns {
+                return *word;
+            }
+        }
+        let mut search = EndgameSearch::new(&self.inputs, self.probes);
+        // 残りの手数で必ず当てられる手がなければ、手数の制限なしで期待値だけを見る
+        let word = search
+            .best(remaining, turns)
+            .or_else(|| search.best(remaining, remaining.len()))
+            .map_or_else(|| self.inner.next_within(turns), |(word, _)| word);
+        *self.cache.borrow_mut() = Some((remaining.to_vec(), turns, word));
+        word
+    }
+    fn next_with_budget(&self, budget: &Budget) -> (Word, bool) {
+        self.next_within_budget(self.max_guesses.saturating_sub(self.guesses), budget)
+    }
+    // 終盤の探索は候補が少ないので止めない
+    fn next_within_budget(&self, turns: usize, budget: &Budget) -> (Word, bool) {
+        match self.inner.remaining().len() > self.threshold {
+            true => self.inner.next_within_budget(turns, budget),
+            false => (self.next_within(turns), true),
+        }
+    }
+    fn turns_left(&self) -> Option<usize> {
+        Some(self.max_guesses.saturating_sub(self.guesses))
+    }
+    fn opener(&self) -> Option<Word> {
+        self.inner.opener()
+    }
+    fn remaining(&self) -> &[Word] {
+        self.inner.remaining()
+    }
+    fn name(&self) -> String {
+        format!("endgame({},{})", self.inner.name(), self.threshold)
+    }
+    fn with_priors(mut self, priors: Priors) -> Self {
+        self.inner = self.inner.with_priors(priors);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tactics::position_freq;
+    fn words(list: &[&str]) -> Vec<Word> {
+        list.iter().map(|s| s.parse().unwrap()).collect()
+    }
+    #[test]
+    fn ight() {
+        let remaining = words(&["fight", "light", "might", "night", "sight", "tight"]);
+        let mut inputs = remaining.clone();
+        inputs.extend(words(&["flmns", "abcde"]));
+        let mut search = EndgameSearch::new(&inputs, 5);
+        // 順に当てていくと平均3.5手、flmnsで分ければ2手
+        let (word, cost) = search.best(&remaining, 6).unwrap();
+        assert_eq!(word, "flmns".parse().unwrap());
+        assert!((cost - 2.0).abs() < 1e-9);
+        assert_eq!(search.best(&remaining, 2).unwrap().0, word);
+        assert!(search.best(&remaining, 1).is_none());
+        let (_, cost) = EndgameSearch::new(&remaining, 0)
+            .best(&remaining, 6)
+            .unwrap();
+        assert!((cost - 3.5).abs() < 1e-9);
+
+        let mut board = Endgame::<position_freq::Board>::new(remaining.clone(), inputs.clone());
+        assert_eq!(board.next(), word);
+        board.filter(&word, &Word::to_status(&word, &remaining[3]));
+        assert_eq!(board.next(), remaining[3]);
+        let board = Endgame::<position_freq::Board>::new(remaining, inputs).with_threshold(3);
+        assert_eq!(board.name(), "endgame(position_freq,3)");
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use super::*;
+    use crate::tactics::position_freq;
+    use crate::tournament::Tournament;
+    use crate::CANDITATES;
+    #[test]
+    #[ignore]
+    fn get_avg_count() {
+        let mut tournament =
+            Tournament::new(CANDITATES.get_canditates(), CANDITATES.get_all_words())
+                .with_opener(Some("soare".parse().unwrap()));
+        tournament.run::<Endgame<position_freq::Board>>();
+        let standing = &tournament.leaderboard()[0];
+        println!(
+            "average count: {}, failures: {}",
+            standing.mean, standing.failures
+        );
+    }
+}
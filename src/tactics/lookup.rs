@@ -0,0 +1,148 @@
+use crate::{
+    enums::{Status, Word},
+    patterns::PatternId,
+    priors::Priors,
+    tactics::{budget::Budget, solver::Solver},
+    tree::{DecisionTree, Node},
+};
+use std::sync::Arc;
+
+// 決定木を引いて打つsolver。木にない局面になったらinnerで計算する
+pub struct Lookup<S: Solver> {
+    inner: S,
+    tree: Option<Arc<DecisionTree>>,
+    // 根からたどったstatus。木を外れたらNone
+    ids: Option<Vec<PatternId>>,
+    // 同じ行をもう一度filterしても木は進めない
+    rows: Vec<(Word, Status)>,
+}
+
+impl<S: Solver> Lookup<S> {
+    // 設定したsolverをそのまま木がないときに使う
+    pub fn wrap(inner: S) -> Lookup<S> {
+        Lookup {
+            inner,
+            tree: None,
+            ids: Some(vec![]),
+            rows: vec![],
+        }
+    }
+    pub fn with_tree(mut self, tree: Option<Arc<DecisionTree>>) -> Self {
+        self.tree = tree;
+        self
+    }
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+    // 今の局面の木のnode
+    pub fn node(&self) -> Option<&Node> {
+        self.tree.as_ref()?.node(self.ids.as_ref()?)
+    }
+}
+
+impl<S: Solver> Solver for Lookup<S> {
+    fn new(canditates: Vec<Word>, inputs: Vec<Word>) -> Self {
+        Lookup::wrap(S::new(canditates, inputs))
+    }
+    fn filter(&mut self, word: &Word, status: &Status) {
+        self.inner.filter(word, status);
+        if self.rows.contains(&(*word, *status)) {
+            return;
+        }
+        self.rows.push((*word, *status));
+        match self.node().is_some_and(|node| node.guess == *word) {
+            true => self.ids.iter_mut().for_each(|ids| ids.push(status.to_id())),
+            false => self.ids = None,
+        }
+    }
+    fn next(&self) -> Word {
+        match self.node() {
+            Some(node) => node.guess,
+            None => self.inner.next(),
+        }
+    }
+    fn next_within(&self, turns: usize) -> Word {
+        match self.node() {
+            Some(node) => node.guess,
+            None => self.inner.next_within(turns),
+        }
+    }
+    fn next_with_budget(&self, budget: &Budget) -> (Word, bool) {
+        match self.node() {
+            Some(node) => (node.guess, true),
+            None => self.inner.next_with_budget(budget),
+        }
+    }
+    fn next_within_budget(&self, turns: usize, budget: &Budget) -> (Word, bool) {
+        match self.node() {
+            Some(node) => (node.guess, true),
+            None => self.inner.next_within_budget(turns, budget),
+        }
+    }
+    fn turns_left(&self) -> Option<usize> {
+        self.inner.turns_left()
+    }
+    fn opener(&self) -> Option<Word> {
+        match &self.tree {
+            Some(tree) => Some(tree.root.guess),
+            None => self.inner.opener(),
+        }
+    }
+    fn remaining(&self) -> &[Word] {
+        self.inner.remaining()
+    }
+    fn name(&self) -> String {
+        match self.tree {
+            Some(_) => format!("lookup({})", self.inner.name()),
+            None => self.inner.name(),
+        }
+    }
+    fn with_priors(mut self, priors: Priors) -> Self {
+        self.inner = self.inner.with_priors(priors);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tactics::position_freq, CANDITATES};
+    #[test]
+    fn lookup() {
+        let canditates = CANDITATES.get_canditates();
+        let inputs = CANDITATES.get_all_words();
+        let first: Word = "soare".parse().unwrap();
+        let tree = DecisionTree::build::<position_freq::Board>(
+            &canditates[..100],
+            &inputs,
+            Some(&first),
+            6,
+        )
+        .unwrap();
+        let tree = Arc::new(tree);
+        let play = |answer: &Word| {
+            let mut board = Lookup::<position_freq::Board>::new(canditates.clone(), inputs.clone())
+                .with_tree(Some(tree.clone()));
+            assert_eq!(board.opener(), Some(first));
+            let mut guesses = vec![];
+            while guesses.last() != Some(answer) && guesses.len() < 6 {
+                let guess = board.next();
+                let status = Word::to_status(&guess, answer);
+                board.filter(&guess, &status);
+                // 同じ行が何度渡されても木は進まない
+                board.filter(&guess, &status);
+                guesses.push(guess);
+            }
+            guesses
+        };
+        // 木にある答えは木のとおりに打つ
+        let path = tree.paths().swap_remove(10);
+        let guesses = play(&path.answer);
+        let steps: Vec<Word> = path.steps.iter().map(|(guess, _)| *guess).collect();
+        assert_eq!(guesses, steps);
+        // 木にない答えでも、外れたところからはinnerで解く
+        let answer = canditates[2000];
+        assert!(tree.paths().iter().all(|path| path.answer != answer));
+        assert_eq!(play(&answer).last(), Some(&answer));
+    }
+}
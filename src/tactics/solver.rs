@@ -1,7 +1,41 @@
-use crate::enums::{Status, Word};
+use crate::{
+    enums::{Status, Word},
+    priors::Priors,
+    tactics::budget::Budget,
+};
 
 pub trait Solver {
     fn new(canditates: Vec<Word>, inputs: Vec<Word>) -> Self;
     fn filter(&mut self, word: &Word, status: &Status);
     fn next(&self) -> Word;
+    // 残りturns手のうちに当てたいときの一手。手数を気にしない戦略はnextと同じ
+    fn next_within(&self, _turns: usize) -> Word {
+        self.next()
+    }
+    // budgetが尽きたらそれまでで一番よい単語を返す。二つ目は最後まで調べたか
+    fn next_with_budget(&self, _budget: &Budget) -> (Word, bool) {
+        (self.next(), true)
+    }
+    // next_withinをbudgetで打ち切るもの。手数を気にしない戦略はnext_with_budgetと同じ
+    fn next_within_budget(&self, _turns: usize, budget: &Budget) -> (Word, bool) {
+        self.next_with_budget(budget)
+    }
+    // nextが残りの手数で変わる戦略は、その手数。変わらなければNone
+    fn turns_left(&self) -> Option<usize> {
+        None
+    }
+    // 何も絞り込んでいないときに決まった一手があれば返す
+    fn opener(&self) -> Option<Word> {
+        None
+    }
+    fn remaining(&self) -> &[Word];
+    // セッションの保存などで使う戦略の名前
+    fn name(&self) -> String;
+    // 答えの確率を考えない戦略はそのまま
+    fn with_priors(self, _priors: Priors) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
 }
@@ -0,0 +1,286 @@
+use crate::enums::{Status, Word};
+use crate::patterns::{bucket_counts, entropy, pattern_id, ALL_GREEN, PATTERN_COUNT};
+use crate::tactics::{budget::Budget, solver::Solver};
+use crate::{get_show_console, Stopwatch};
+use std::{cmp::Ordering, collections::HashMap};
+
+pub const DEFAULT_DEPTH: usize = 2;
+pub const DEFAULT_WIDTH: usize = 10;
+
+// 平均の手数ではなく、残りの手数のうちに当てられる確率を最大にする
+pub struct Board {
+    remaining_canditates: Vec<Word>,
+    input_canditates: Vec<Word>,
+    max_guesses: usize,
+    guesses: usize,
+    // 先読みする手数と、一つの局面で調べる単語の数
+    depth: usize,
+    width: usize,
+}
+
+impl Board {
+    pub fn with_max_guesses(mut self, max_guesses: usize) -> Board {
+        self.max_guesses = max_guesses;
+        self
+    }
+    pub fn with_depth(mut self, depth: usize) -> Board {
+        self.depth = depth;
+        self
+    }
+    pub fn with_width(mut self, width: usize) -> Board {
+        self.width = width;
+        self
+    }
+    // turns手のうちに当てられる確率の一番大きい単語と、その確率
+    pub fn best_within(&self, turns: usize) -> (Word, f64) {
+        self.search_within(turns, None).0
+    }
+    // budgetが尽きたら、それまでに調べた一手目の中で一番よいもの。二つ目は最後まで調べたか
+    fn search_within(&self, turns: usize, budget: Option<&Budget>) -> ((Word, f64), bool) {
+        let mut search = Search {
+            inputs: &self.input_canditates,
+            width: self.width,
+            memo: HashMap::new(),
+        };
+        search.best(&self.remaining_canditates, turns, self.depth, budget)
+    }
+}
+
+struct Search<'a> {
+    inputs: &'a [Word],
+    width: usize,
+    memo: HashMap<(Vec<Word>, usize, usize), f64>,
+}
+
+impl Search<'_> {
+    // 平均情報量の大きい順に、入力できる単語と候補の単語をwidth個ずつ
+    fn ordered(&self, remaining: &[Word]) -> Vec<Word> {
+        fn top(words: &[Word], remaining: &[Word], width: usize) -> Vec<(Word, f64)> {
+            let mut scores: Vec<(Word, f64)> = words
+                .iter()
+                .map(|word| (*word, entropy(&bucket_counts(word, remaining))))
+                .collect();
+            scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+            scores.truncate(width);
+            scores
+        }
+        let mut guesses = top(remaining, remaining, self.width);
+        top(self.inputs, remaining, self.width)
+            .into_iter()
+            .filter(|(word, _)| !remaining.contains(word))
+            .for_each(|guess| guesses.push(guess));
+        guesses.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        guesses.into_iter().map(|(word, _)| word).collect()
+    }
+    // budgetは一手目を選ぶときだけ見る。先読みの中では止めない
+    fn best(
+        &mut self,
+        remaining: &[Word],
+        turns: usize,
+        depth: usize,
+        budget: Option<&Budget>,
+    ) -> ((Word, f64), bool) {
+        // 矛盾した入力で候補がなくなっても落ちない
+        let (word, probability) = match (remaining.len(), turns) {
+            (0, _) => (self.inputs[0], 0.0),
+            (_, 0) => (remaining[0], 0.0),
+            (1, _) => (remaining[0], 1.0),
+            (n, 1) => (remaining[0], 1.0 / n as f64),
+            (2, _) => (remaining[0], 1.0),
+            _ => return self.search(remaining, turns, depth, budget),
+        };
+        ((word, probability), true)
+    }
+    fn search(
+        &mut self,
+        remaining: &[Word],
+        turns: usize,
+        depth: usize,
+        budget: Option<&Budget>,
+    ) -> ((Word, f64), bool) {
+        let n = remaining.len() as f64;
+        let mut best: Option<(Word, f64)> = None;
+        for guess in self.ordered(remaining) {
+            // 一つ目は必ず調べる
+            if let (Some(best), Some(true)) = (best, budget.map(|b| b.is_over())) {
+                return (best, false);
+            }
+            let mut groups: Vec<Vec<Word>> = vec![vec![]; PATTERN_COUNT];
+            remaining
+                .iter()
+                .for_each(|answer| groups[pattern_id(&guess, answer) as usize].push(*answer));
+            let probability: f64 = groups
+                .iter()
+                .enumerate()
+                .filter(|(_, group)| !group.is_empty())
+                .map(|(id, group)| {
+                    let weight = group.len() as f64 / n;
+                    match id == ALL_GREEN as usize {
+                        true => weight,
+                        false => weight * self.value(group, turns - 1, depth),
+                    }
+                })
+                .sum();
+            // 同じ確率なら平均情報量の大きいほう
+            if best.is_none_or(|(_, p)| probability > p + 1e-12) {
+                best = Some((guess, probability));
+            }
+            if probability >= 1.0 - 1e-12 {
+                break;
+            }
+        }
+        (best.unwrap_or((remaining[0], 0.0)), true)
+    }
+    fn value(&mut self, remaining: &[Word], turns: usize, depth: usize) -> f64 {
+        if depth <= 1 {
+            return estimate(remaining.len(), turns);
+        }
+        let key = (remaining.to_vec(), turns, depth);
+        if let Some(value) = self.memo.get(&key) {
+            return *value;
+        }
+        let ((_, value), _) = self.best(remaining, turns, depth - 1, None);
+        self.memo.insert(key, value);
+        value
+    }
+}
+
+// 先読みしない局面の見積もり。一手ごとに候補がおよそ1/10になるとして、最後の一手で当てる確率
+fn estimate(size: usize, turns: usize) -> f64 {
+    match (size, turns) {
+        (_, 0) => 0.0,
+        (1, _) => 1.0,
+        (2, t) if t >= 2 => 1.0,
+        (n, t) => (10f64.powi(t.min(10) as i32 - 1) / n as f64).min(1.0),
+    }
+}
+
+impl Solver for Board {
+    fn new(canditates: Vec<Word>, inputs: Vec<Word>) -> Board {
+        Board {
+            remaining_canditates: canditates,
+            input_canditates: inputs,
+            max_guesses: 6,
+            guesses: 0,
+            depth: DEFAULT_DEPTH,
+            width: DEFAULT_WIDTH,
+        }
+    }
+    fn filter(&mut self, word: &Word, status: &Status) {
+        self.remaining_canditates
+            .retain(|answer| status == &Word::to_status(word, answer));
+        self.guesses += 1;
+    }
+    fn next(&self) -> Word {
+        self.next_within(self.max_guesses.saturating_sub(self.guesses).max(1))
+    }
+    fn turns_left(&self) -> Option<usize> {
+        Some(self.max_guesses.saturating_sub(self.guesses).max(1))
+    }
+    fn next_with_budget(&self, budget: &Budget) -> (Word, bool) {
+        self.next_within_budget(self.max_guesses.saturating_sub(self.guesses).max(1), budget)
+    }
+    // 一手目の候補を一つ調べるごとにbudgetを見る
+    fn next_within_budget(&self, turns: usize, budget: &Budget) -> (Word, bool) {
+        let start = Stopwatch::start();
+        let ((word, probability), completed) = self.search_within(turns.max(1), Some(budget));
+        if get_show_console() {
+            console!("next word: {}", word);
+            console!("win probability within {}: {}", turns, probability);
+            console!("next word time: {:?}", start.elapsed());
+        }
+        (word, completed)
+    }
+    fn next_within(&self, turns: usize) -> Word {
+        self.next_within_budget(turns, &Budget::unlimited()).0
+    }
+    fn remaining(&self) -> &[Word] {
+        &self.remaining_canditates
+    }
+    // 先読みの設定で一手が変わるので、表のkeyに入るように名前に含める
+    fn name(&self) -> String {
+        format!("win_prob({},{})", self.depth, self.width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    fn words(list: &[&str]) -> Vec<Word> {
+        list.iter().map(|s| s.parse().unwrap()).collect()
+    }
+    #[test]
+    fn within() {
+        let remaining = words(&["fight", "light", "might", "night", "sight", "tight"]);
+        let mut inputs = remaining.clone();
+        inputs.push("flmns".parse().unwrap());
+        let board = Board::new(remaining.clone(), inputs);
+        // 二手で必ず当てるには候補の外の単語で分けるしかない
+        let (word, probability) = board.best_within(2);
+        assert_eq!(word, "flmns".parse().unwrap());
+        assert!((probability - 1.0).abs() < 1e-9);
+        // 一手なら当てずっぽう
+        let (word, probability) = board.best_within(1);
+        assert!(remaining.contains(&word));
+        assert!((probability - 1.0 / 6.0).abs() < 1e-9);
+        // 候補の中だけなら、三手で当てられるのは半分
+        let (_, probability) = Board::new(remaining.clone(), remaining.clone())
+            .with_depth(3)
+            .best_within(3);
+        assert!((probability - 0.5).abs() < 1e-9);
+
+        let mut board = Board::new(remaining.clone(), remaining.clone()).with_max_guesses(3);
+        board.filter(
+            &remaining[0],
+            &Word::to_status(&remaining[0], &remaining[5]),
+        );
+        assert_eq!(board.remaining().len(), 5);
+        assert!(board.remaining().contains(&board.next()));
+        assert_eq!(board.name(), "win_prob(2,10)");
+        assert_ne!(board.name(), board.with_depth(3).name());
+
+        // 候補がなくなっても入力できる単語を返す
+        let mut board = Board::new(remaining.clone(), remaining.clone());
+        board.filter(&remaining[0], &"ggggg".parse().unwrap());
+        board.filter(&remaining[1], &"ggggg".parse().unwrap());
+        assert!(board.remaining().is_empty());
+        assert_eq!(board.next(), remaining[0]);
+    }
+    #[test]
+    fn budget() {
+        let remaining = words(&["fight", "light", "might", "night", "sight", "tight"]);
+        let board = Board::new(remaining.clone(), remaining.clone()).with_depth(3);
+        let (word, completed) = board.next_within_budget(3, &Budget::unlimited());
+        assert!(completed);
+        assert_eq!(word, board.best_within(3).0);
+        // 止められたら、一つ目の候補だけ調べて途中までだと返す
+        let (first, completed) =
+            board.next_within_budget(3, &Budget::with_time_limit(Duration::ZERO));
+        assert!(!completed);
+        assert!(remaining.contains(&first));
+        // 調べるまでもない局面は最後まで調べたことになる
+        let (_, completed) = board.next_within_budget(1, &Budget::with_time_limit(Duration::ZERO));
+        assert!(completed);
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use super::*;
+    use crate::tournament::Tournament;
+    use crate::CANDITATES;
+    #[test]
+    #[ignore]
+    fn get_failures() {
+        let mut tournament =
+            Tournament::new(CANDITATES.get_canditates(), CANDITATES.get_all_words())
+                .with_opener(Some("soare".parse().unwrap()));
+        tournament.run::<Board>();
+        let standing = &tournament.leaderboard()[0];
+        println!(
+            "average count: {}, failures: {}",
+            standing.mean, standing.failures
+        );
+    }
+}
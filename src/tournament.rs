@@ -0,0 +1,346 @@
+use crate::{
+    enums::Word,
+    game::{Game, GameState},
+    quietly,
+    share::{ShareGrid, Theme},
+    tactics::solver::Solver,
+    Stopwatch,
+};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+// 一つの戦略で全部の答えを解いた結果。gamesは答えと同じ順
+pub struct Entry {
+    pub name: String,
+    pub games: Vec<Game>,
+    pub max_guesses: usize,
+    // openerを除いて、solverが選んだ手の数とその手のnextとfilterにかかった実時間
+    pub moves: usize,
+    pub time: Duration,
+}
+
+impl Entry {
+    // 当てた手数。当てられなければNone
+    pub fn guesses(&self) -> Vec<Option<usize>> {
+        self.games
+            .iter()
+            .map(|game| match game.state() {
+                GameState::Won(count) => Some(count),
+                _ => None,
+            })
+            .collect()
+    }
+    pub fn grids(&self) -> Vec<String> {
+        self.games
+            .iter()
+            .map(|game| {
+                let mut grid = ShareGrid::from_game(&game.answer().to_string(), game);
+                grid.max_guesses = self.max_guesses;
+                grid.render(Theme::Dark)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Standing {
+    pub name: String,
+    // 当てた答えについての手数
+    pub mean: f64,
+    pub median: f64,
+    pub p95: usize,
+    pub max: usize,
+    pub failures: usize,
+    // 一つのスレッドで測った実時間で、CPU時間ではない
+    pub wall_ms_per_move: f64,
+    // 手数ごとの答えの数
+    pub distribution: BTreeMap<usize, usize>,
+}
+
+// 答えごとにaとbの手数を比べたもの。当てられなかったほうが負け
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Pairing {
+    pub a: String,
+    pub b: String,
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+    // 勝ち負けの数の差の符号検定（正規近似）
+    pub z: f64,
+    pub p_value: f64,
+}
+
+// いくつもの戦略を同じ答えと同じopenerで解かせて比べる
+pub struct Tournament {
+    answers: Vec<Word>,
+    inputs: Vec<Word>,
+    dictionary: Arc<HashSet<Word>>,
+    opener: Option<Word>,
+    max_guesses: usize,
+    entries: Vec<Entry>,
+}
+
+impl Tournament {
+    pub fn new(answers: Vec<Word>, inputs: Vec<Word>) -> Tournament {
+        Tournament {
+            dictionary: Arc::new(inputs.iter().cloned().collect()),
+            answers,
+            inputs,
+            opener: None,
+            max_guesses: 6,
+            entries: vec![],
+        }
+    }
+    pub fn with_opener(mut self, opener: Option<Word>) -> Tournament {
+        self.opener = opener;
+        self
+    }
+    // usize::MAXなら当てるまで続ける
+    pub fn with_max_guesses(mut self, max_guesses: usize) -> Tournament {
+        self.max_guesses = max_guesses;
+        self
+    }
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+    pub fn run<S: Solver>(&mut self) -> &Entry {
+        let (answers, inputs) = (self.answers.clone(), self.inputs.clone());
+        self.run_with(|| S::new(answers.clone(), inputs.clone()))
+    }
+    // 答えごとにnew_boardでboardを作る。表を共有するときなど
+    pub fn run_with<S: Solver>(&mut self, new_board: impl Fn() -> S) -> &Entry {
+        let mut entry = Entry {
+            name: String::new(),
+            games: vec![],
+            max_guesses: self.max_guesses,
+            moves: 0,
+            time: Duration::ZERO,
+        };
+        let mut current = 0;
+        for (index, answer) in self.answers.iter().enumerate() {
+            let mut board = new_board();
+            entry.name = board.name();
+            let mut game =
+                Game::new(*answer, self.dictionary.clone()).with_max_guesses(self.max_guesses);
+            // openerの一手とそのfilterは、時間にも手の数にも数えない
+            if let Some(opener) = &self.opener {
+                if let Ok(status) = game.guess(opener) {
+                    quietly(|| board.filter(opener, &status));
+                }
+            }
+            let played = game.history().len();
+            let stopwatch = Stopwatch::start();
+            // 入力できない単語を選んだら、そこで負け
+            let _ = quietly(|| game.autoplay(&mut board, None));
+            entry.time += stopwatch.elapsed();
+            entry.moves += game.history().len() - played;
+            entry.games.push(game);
+            let percentage = (index + 1) * 100 / self.answers.len() / 5 * 5;
+            if percentage > current {
+                current = percentage;
+                console!("{} {}%", entry.name, current);
+            }
+        }
+        self.entries.push(entry);
+        self.entries.last().unwrap()
+    }
+    // 失敗の少ない順、平均の手数の少ない順
+    pub fn leaderboard(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let guesses = entry.guesses();
+                let mut solved: Vec<usize> = guesses.iter().flatten().cloned().collect();
+                solved.sort_unstable();
+                let mut distribution = BTreeMap::new();
+                solved
+                    .iter()
+                    .for_each(|count| *distribution.entry(*count).or_insert(0) += 1);
+                let n = solved.len();
+                Standing {
+                    name: entry.name.clone(),
+                    mean: match n {
+                        0 => f64::NAN,
+                        _ => solved.iter().sum::<usize>() as f64 / n as f64,
+                    },
+                    median: match n {
+                        0 => f64::NAN,
+                        _ => (solved[(n - 1) / 2] + solved[n / 2]) as f64 / 2.0,
+                    },
+                    p95: match n {
+                        0 => 0,
+                        _ => solved[((n as f64 * 0.95).ceil() as usize).max(1) - 1],
+                    },
+                    max: solved.last().cloned().unwrap_or(0),
+                    failures: guesses.len() - n,
+                    wall_ms_per_move: entry.time.as_secs_f64() * 1000.0 / entry.moves.max(1) as f64,
+                    distribution,
+                }
+            })
+            .collect();
+        standings.sort_by(|a, b| {
+            a.failures
+                .cmp(&b.failures)
+                .then(a.mean.partial_cmp(&b.mean).unwrap_or(Ordering::Equal))
+        });
+        standings
+    }
+    pub fn pairwise(&self) -> Vec<Pairing> {
+        let mut pairings = vec![];
+        for (i, a) in self.entries.iter().enumerate() {
+            for b in &self.entries[i + 1..] {
+                let (mut wins, mut losses, mut ties) = (0, 0, 0);
+                a.guesses()
+                    .iter()
+                    .zip(b.guesses().iter())
+                    .for_each(|(x, y)| match (x, y) {
+                        (Some(x), Some(y)) if x < y => wins += 1,
+                        (Some(x), Some(y)) if x > y => losses += 1,
+                        (Some(_), None) => wins += 1,
+                        (None, Some(_)) => losses += 1,
+                        _ => ties += 1,
+                    });
+                let (z, p_value) = sign_test(wins, losses);
+                pairings.push(Pairing {
+                    a: a.name.clone(),
+                    b: b.name.clone(),
+                    wins,
+                    losses,
+                    ties,
+                    z,
+                    p_value,
+                });
+            }
+        }
+        pairings
+    }
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "answers: {}, opener: {}",
+                self.answers.len(),
+                self.opener.map_or("-".to_string(), |word| word.to_string())
+            ),
+            String::new(),
+            "| tactic | mean | median | p95 | max | failures | wall ms/move |".to_string(),
+            "|---|---|---|---|---|---|---|".to_string(),
+        ];
+        self.leaderboard().iter().for_each(|s| {
+            lines.push(format!(
+                "| {} | {:.4} | {:.1} | {} | {} | {} | {:.3} |",
+                s.name, s.mean, s.median, s.p95, s.max, s.failures, s.wall_ms_per_move
+            ))
+        });
+        let pairings = self.pairwise();
+        if !pairings.is_empty() {
+            lines.push(String::new());
+            lines.push("| a | b | wins | losses | ties | z | p |".to_string());
+            lines.push("|---|---|---|---|---|---|---|".to_string());
+            pairings.iter().for_each(|p| {
+                lines.push(format!(
+                    "| {} | {} | {} | {} | {} | {:.2} | {:.4} |",
+                    p.a, p.b, p.wins, p.losses, p.ties, p.z, p.p_value
+                ))
+            });
+        }
+        lines.join("\n") + "\n"
+    }
+    pub fn to_json(&self) -> String {
+        json!({
+            "answers": self.answers.len(),
+            "opener": self.opener.map(|word| word.to_string()),
+            "max_guesses": self.max_guesses,
+            "leaderboard": self.leaderboard(),
+            "pairwise": self.pairwise(),
+        })
+        .to_string()
+    }
+}
+
+// 引き分けを除いた勝ち負けが半々かどうか。連続補正つきの正規近似で両側のp値
+fn sign_test(wins: usize, losses: usize) -> (f64, f64) {
+    let n = (wins + losses) as f64;
+    if n == 0.0 {
+        return (0.0, 1.0);
+    }
+    let diff = wins as f64 - losses as f64;
+    let z = diff.signum() * (diff.abs() - 1.0).max(0.0) / n.sqrt();
+    (z, erfc(z.abs() / std::f64::consts::SQRT_2))
+}
+
+// Abramowitz and Stegun 7.1.26。誤差は1.5e-7まで
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    poly * (-x * x).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tactics::{avg_info_max, position_freq},
+        CANDITATES,
+    };
+    #[test]
+    fn tournament() {
+        let mut tournament = Tournament::new(
+            CANDITATES.get_canditates()[..40].to_vec(),
+            CANDITATES.get_all_words(),
+        )
+        .with_opener(Some("soare".parse().unwrap()));
+        tournament.run::<position_freq::Board>();
+        tournament.run::<avg_info_max::Board>();
+        let leaderboard = tournament.leaderboard();
+        assert_eq!(leaderboard.len(), 2);
+        leaderboard.iter().for_each(|s| {
+            assert!(s.mean >= 2.0 && s.median <= s.p95 as f64 && s.p95 <= s.max);
+            assert_eq!(s.distribution.values().sum::<usize>() + s.failures, 40);
+        });
+        let pairings = tournament.pairwise();
+        assert_eq!(pairings.len(), 1);
+        let p = &pairings[0];
+        assert_eq!(
+            (p.a.as_str(), p.b.as_str()),
+            ("position_freq", "avg_info_max")
+        );
+        assert_eq!(p.wins + p.losses + p.ties, 40);
+        assert!(p.p_value > 0.0 && p.p_value <= 1.0);
+        assert!(tournament.to_markdown().contains("| avg_info_max |"));
+        let json: serde_json::Value = serde_json::from_str(&tournament.to_json()).unwrap();
+        assert_eq!(json["leaderboard"].as_array().unwrap().len(), 2);
+        let entry = &tournament.entries()[0];
+        let played: usize = entry.games.iter().map(|game| game.history().len()).sum();
+        assert_eq!(entry.moves, played - 40);
+
+        // 一つも当てられなければ平均も中央値もない
+        let mut tournament = Tournament::new(
+            CANDITATES.get_canditates()[..3].to_vec(),
+            CANDITATES.get_all_words(),
+        )
+        .with_opener(Some("soare".parse().unwrap()))
+        .with_max_guesses(1);
+        tournament.run::<position_freq::Board>();
+        let standing = &tournament.leaderboard()[0];
+        assert_eq!(standing.failures, 3);
+        assert!(standing.mean.is_nan() && standing.median.is_nan());
+        // 手の数にもgridにも、tournamentの手数の上限を使う
+        let entry = &tournament.entries()[0];
+        assert_eq!(entry.moves, 0);
+        assert!(entry.grids()[0].starts_with("cigar X/1"));
+
+        assert_eq!(sign_test(0, 0), (0.0, 1.0));
+        // 60勝40敗はz=1.9で、5%では有意でない
+        let (z, p) = sign_test(60, 40);
+        assert!((z - 1.9).abs() < 1e-9 && (p - 0.0574).abs() < 1e-3);
+    }
+}
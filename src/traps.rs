@@ -0,0 +1,283 @@
+use crate::{
+    enums::{Alphabet, Status, Word},
+    patterns::{bucket_counts, pattern_id, ALL_GREEN},
+    quietly,
+    tactics::solver::Solver,
+};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+// この数以上の単語が一、二文字違いで並び、残りの手数より多ければ罠とみなす
+pub const MIN_CLUSTER: usize = 4;
+
+// 一、二文字だけが違う単語の集まり（-IGHTや-ATCHのようなもの）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    // 単語ごとに違う文字の位置
+    pub positions: Vec<usize>,
+    pub words: Vec<Word>,
+}
+
+impl Cluster {
+    // 違う文字を_にした形
+    pub fn shape(&self, display: &str) -> String {
+        display
+            .chars()
+            .enumerate()
+            .map(|(i, c)| if self.positions.contains(&i) { '_' } else { c })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trap {
+    pub cluster: Cluster,
+    // 今の戦略で、残りの手数のうちに当てられない確率（候補はどれも同じ確率とする）
+    pub failure: f64,
+    pub failures: Vec<Word>,
+    // clusterを一番細かく分ける単語と、分けたあとの組の数
+    pub probe: Option<(Word, usize)>,
+}
+
+// 大きい順。ほかのclusterに含まれるものは除く
+pub fn find_clusters(remaining: &[Word], min_size: usize) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = vec![];
+    for wildcards in 1..=2 {
+        for positions in (0..5).combinations(wildcards) {
+            let mut groups: HashMap<[Alphabet; 5], Vec<Word>> = HashMap::new();
+            remaining.iter().for_each(|word| {
+                let mut key = word.0;
+                positions.iter().for_each(|i| key[*i] = Alphabet::OMEGA);
+                groups.entry(key).or_default().push(*word);
+            });
+            clusters.extend(
+                groups
+                    .into_values()
+                    .filter(|words| words.len() >= min_size)
+                    .map(|words| Cluster {
+                        positions: positions.clone(),
+                        words,
+                    }),
+            );
+        }
+    }
+    // 同じ大きさなら違う文字の少ないほうを先に
+    clusters.sort_by(|a, b| {
+        b.words
+            .len()
+            .cmp(&a.words.len())
+            .then(a.positions.len().cmp(&b.positions.len()))
+    });
+    let mut found: Vec<Cluster> = vec![];
+    for cluster in clusters {
+        let covered = found
+            .iter()
+            .any(|other| cluster.words.iter().all(|w| other.words.contains(w)));
+        if !covered {
+            found.push(cluster);
+        }
+    }
+    found
+}
+
+// 分けられる組の数が多く、一番大きい組が小さい単語
+pub fn best_probe(cluster: &[Word], inputs: &[Word]) -> Option<(Word, usize)> {
+    inputs
+        .iter()
+        .map(|word| {
+            let buckets = bucket_counts(word, cluster);
+            let groups = buckets.iter().filter(|c| **c > 0).count();
+            let largest = *buckets.iter().max().unwrap();
+            (*word, groups, largest, cluster.contains(word))
+        })
+        .max_by(|a, b| {
+            a.1.cmp(&b.1)
+                .then(b.2.cmp(&a.2))
+                .then(a.3.cmp(&b.3))
+                .then(b.0 .0.cmp(&a.0 .0))
+        })
+        .filter(|(_, groups, _, _)| *groups > 1)
+        .map(|(word, groups, _, _)| (word, groups))
+}
+
+// new_boardで作った今の局面のboardで、turns手のうちに当てられない答え。
+// 同じ局面ではboardを作りなおしてこれまでの行をfilterし、next_withinを一度だけ呼ぶ
+pub fn failures<S: Solver>(new_board: impl Fn() -> S, turns: usize) -> Vec<Word> {
+    fn walk<S: Solver>(
+        new_board: &dyn Fn() -> S,
+        rows: &mut Vec<(Word, Status)>,
+        answers: &[Word],
+        turns: usize,
+        failed: &mut Vec<Word>,
+    ) {
+        if turns == 0 {
+            failed.extend_from_slice(answers);
+            return;
+        }
+        let mut board = new_board();
+        rows.iter()
+            .for_each(|(word, status)| board.filter(word, status));
+        let guess = board.next_within(turns);
+        let remaining = board.remaining();
+        let groups = answers
+            .iter()
+            .into_group_map_by(|answer| pattern_id(&guess, answer));
+        for (id, group) in groups.into_iter().sorted_by_key(|(id, _)| *id) {
+            if id == ALL_GREEN {
+                continue;
+            }
+            let group: Vec<Word> = group.into_iter().cloned().collect();
+            // 何も絞り込めない戦略は残りの手数を使い切っても当てられない
+            let kept = remaining
+                .iter()
+                .filter(|word| pattern_id(&guess, word) == id)
+                .count();
+            if kept == remaining.len() && !remaining.contains(&guess) {
+                failed.extend(group);
+                continue;
+            }
+            rows.push((guess, Status::from_id(id)));
+            walk(new_board, rows, &group, turns - 1, failed);
+            rows.pop();
+        }
+    }
+    let mut failed = vec![];
+    quietly(|| {
+        let remaining = new_board().remaining().to_vec();
+        walk(&new_board, &mut vec![], &remaining, turns, &mut failed)
+    });
+    failed.sort_by_key(|word| word.0);
+    failed
+}
+
+// 一番大きい罠について、失敗する確率と分けるための単語を調べる。new_boardはfailuresと同じ
+pub fn detect<S: Solver>(
+    remaining: &[Word],
+    inputs: &[Word],
+    turns: usize,
+    min_size: usize,
+    new_board: impl Fn() -> S,
+) -> Option<Trap> {
+    // 一つずつ入れても残りの手数で足りるなら罠ではない
+    let cluster = find_clusters(remaining, min_size)
+        .into_iter()
+        .next()
+        .filter(|cluster| cluster.words.len() > turns)?;
+    let failures = failures(new_board, turns);
+    Some(Trap {
+        failure: failures.len() as f64 / remaining.len() as f64,
+        failures,
+        probe: best_probe(&cluster.words, inputs),
+        cluster,
+    })
+}
+
+// 評価用: Sで全部の答えを解いて、途中で罠に入った答えを集める
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrapCase {
+    pub answer: Word,
+    // 罠が見つかった手（1始まり）と、そのときのclusterの大きさ
+    pub turn: usize,
+    pub cluster: usize,
+    // max_guessesで当てられなかったか
+    pub failed: bool,
+}
+
+pub fn trap_prone<S: Solver>(
+    answers: &[Word],
+    inputs: &[Word],
+    first: Option<&Word>,
+    max_guesses: usize,
+    min_size: usize,
+) -> Vec<TrapCase> {
+    answers
+        .iter()
+        .filter_map(|answer| {
+            let mut board = S::new(answers.to_vec(), inputs.to_vec());
+            let mut trap: Option<(usize, usize)> = None;
+            let mut solved = false;
+            for turn in 1..=max_guesses {
+                // 一手目の前は答えの候補全部なので数えない
+                if trap.is_none() && turn > 1 {
+                    trap = find_clusters(board.remaining(), min_size)
+                        .into_iter()
+                        .find(|cluster| {
+                            cluster.words.len() > max_guesses + 1 - turn
+                                && cluster.words.contains(answer)
+                        })
+                        .map(|cluster| (turn, cluster.words.len()));
+                }
+                let guess = match (turn, first) {
+                    (1, Some(first)) => *first,
+                    _ => board.next(),
+                };
+                if &guess == answer {
+                    solved = true;
+                    break;
+                }
+                board.filter(&guess, &Word::to_status(&guess, answer));
+            }
+            trap.map(|(turn, cluster)| TrapCase {
+                answer: *answer,
+                turn,
+                cluster,
+                failed: !solved,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tactics::position_freq;
+    fn words(list: &[&str]) -> Vec<Word> {
+        list.iter().map(|s| s.parse().unwrap()).collect()
+    }
+    #[test]
+    fn ight() {
+        let remaining = words(&[
+            "fight", "light", "might", "night", "sight", "tight", "right", "wight",
+        ]);
+        let mut inputs = remaining.clone();
+        inputs.extend(words(&["flmns", "abcde"]));
+        let clusters = find_clusters(&remaining, MIN_CLUSTER);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].positions, vec![0]);
+        assert_eq!(clusters[0].shape("fight"), "_ight");
+        assert!(
+            find_clusters(&words(&["fight", "crane", "soare", "light"]), 2)
+                .iter()
+                .all(|c| c.words.len() == 2)
+        );
+
+        let board = |inputs: &[Word]| {
+            let (remaining, inputs) = (remaining.clone(), inputs.to_vec());
+            move || position_freq::Board::new(remaining.clone(), inputs.clone())
+        };
+        // 候補の中から一つずつ当てていくと三手では三つしか当たらない
+        let trap = detect(&remaining, &remaining, 3, MIN_CLUSTER, board(&remaining)).unwrap();
+        assert_eq!(trap.failures.len(), 5);
+        assert!((trap.failure - 5.0 / 8.0).abs() < 1e-9);
+        let trap = detect(&remaining, &inputs, 3, MIN_CLUSTER, board(&inputs)).unwrap();
+        assert_eq!(trap.probe, Some(("flmns".parse().unwrap(), 6)));
+        assert!(trap.failure < 5.0 / 8.0);
+        assert!(detect(&inputs[..3], &inputs, 3, MIN_CLUSTER, board(&inputs)).is_none());
+        // 絞り込んだあとのboardからでも、その局面の残りについて調べる
+        let mut all = remaining.clone();
+        all.extend(words(&["crane", "soare"]));
+        let filtered = || {
+            let mut board = position_freq::Board::new(all.clone(), remaining.clone());
+            // eを含むcraneとsoareだけが消える
+            board.filter(&"eeeee".parse().unwrap(), &"_____".parse().unwrap());
+            board
+        };
+        assert_eq!(failures(filtered, 3), failures(board(&remaining), 3));
+
+        let cases = trap_prone::<position_freq::Board>(&remaining, &remaining, None, 6, 4);
+        // 一手目で当たったもの以外
+        assert_eq!(cases.len(), remaining.len() - 1);
+        assert!(cases.iter().any(|case| case.failed));
+        assert!(cases.iter().all(|case| case.turn == 2));
+    }
+}
@@ -0,0 +1,677 @@
+use crate::{
+    enums::{Status, StatusChar, Word},
+    patterns::{pattern_id, PatternId, ALL_GREEN},
+    quietly,
+    tactics::{
+        cached::{Cached, RecommendationCache},
+        solver::Solver,
+    },
+    variants::Variant,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path as FilePath,
+    str::FromStr,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    // 公開されているwordleの木と同じ、一行に一つの答えを字下げで並べる形
+    Text,
+    // Graphviz
+    Dot,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "json" => Ok(Format::Json),
+            "text" => Ok(Format::Text),
+            "dot" => Ok(Format::Dot),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+// 一手ごとのnode。ggggg以外のstatusごとに次のnodeへの枝がある
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub guess: Word,
+    // このnodeを通る答えの数
+    pub answers: usize,
+    // guessがそのまま答えのことがあるか
+    pub solved: bool,
+    // PatternIdの順
+    pub children: Vec<(PatternId, Node)>,
+    // 手数を使い切っても当てられなかった答え
+    pub failed: Vec<Word>,
+}
+
+impl Node {
+    fn new(guess: Word) -> Node {
+        Node {
+            guess,
+            answers: 0,
+            solved: false,
+            children: vec![],
+            failed: vec![],
+        }
+    }
+    // 一局の(guess, status)の並びを足す
+    fn insert(&mut self, answer: &Word, path: &[(Word, PatternId)]) -> Result<(), String> {
+        let (guess, id) = path[0];
+        if guess != self.guess {
+            return Err(format!(
+                "{}: {} is played where the tree already has {}",
+                answer, guess, self.guess
+            ));
+        }
+        self.answers += 1;
+        if id == ALL_GREEN {
+            self.solved = true;
+            return Ok(());
+        }
+        let next = match path.get(1) {
+            Some((next, _)) => *next,
+            None => {
+                self.failed.push(*answer);
+                return Ok(());
+            }
+        };
+        let index = match self.children.binary_search_by_key(&id, |(id, _)| *id) {
+            Ok(index) => index,
+            Err(index) => {
+                self.children.insert(index, (id, Node::new(next)));
+                index
+            }
+        };
+        self.children[index].1.insert(answer, &path[1..])
+    }
+    fn paths(&self, prefix: &mut Vec<(Word, PatternId)>, paths: &mut Vec<Path>) {
+        // 子、当たり、失敗をstatusの順に並べる
+        let mut ends: Vec<(PatternId, Option<&Node>, Option<Word>)> = self
+            .children
+            .iter()
+            .map(|(id, child)| (*id, Some(child), None))
+            .collect();
+        if self.solved {
+            ends.push((ALL_GREEN, None, Some(self.guess)));
+        }
+        self.failed.iter().for_each(|answer| {
+            ends.push((pattern_id(&self.guess, answer), None, Some(*answer)));
+        });
+        ends.sort_by_key(|(id, _, _)| *id);
+        for (id, child, answer) in ends {
+            prefix.push((self.guess, id));
+            match (child, answer) {
+                (Some(child), _) => child.paths(prefix, paths),
+                (None, Some(answer)) => paths.push(Path {
+                    answer,
+                    steps: prefix.clone(),
+                }),
+                (None, None) => unreachable!(),
+            }
+            prefix.pop();
+        }
+    }
+    fn to_json(&self, variant: &dyn Variant) -> Value {
+        json!({
+            "guess": variant.display_word(&self.guess),
+            "answers": self.answers,
+            "solved": self.solved,
+            "children": self.children.iter().map(|(id, child)| {
+                let mut value = child.to_json(variant);
+                value["status"] = json!(Status::from_id(*id).to_string());
+                value
+            }).collect::<Vec<_>>(),
+            "failed": self.failed.iter().map(|word| variant.display_word(word)).collect::<Vec<_>>(),
+        })
+    }
+}
+
+// 根から一つの答えまでの道。最後のstatusがgggggでなければ当てられなかった
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub answer: Word,
+    pub steps: Vec<(Word, PatternId)>,
+}
+
+impl Path {
+    pub fn solved(&self) -> bool {
+        self.steps.last().is_some_and(|(_, id)| *id == ALL_GREEN)
+    }
+}
+
+// 戦略を全部の答えで動かして、局面ごとにまとめたもの
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecisionTree {
+    pub root: Node,
+    pub max_guesses: usize,
+}
+
+// 木を答えのリストと照らし合わせた結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+    // 木でたどり着けない答え
+    pub missing: Vec<Word>,
+    // 答えのリストにないのに木にある答え
+    pub unknown: Vec<Word>,
+    // 木の二つ以上の葉にある答え
+    pub duplicates: Vec<Word>,
+    // statusがWord::to_statusと合わない答えと手数
+    pub inconsistent: Vec<(Word, usize)>,
+    // 入力できない単語
+    pub not_in_word_list: Vec<Word>,
+    // 手数を使い切っても当てられない答え
+    pub failed: Vec<Word>,
+    pub depth: usize,
+    // 当てた答えの手数の平均
+    pub average: f64,
+}
+
+impl Verification {
+    // 答えのリストと木の答えがちょうど一対一でなければ通さない
+    pub fn is_ok(&self, max_guesses: usize) -> bool {
+        self.missing.is_empty()
+            && self.unknown.is_empty()
+            && self.duplicates.is_empty()
+            && self.inconsistent.is_empty()
+            && self.not_in_word_list.is_empty()
+            && self.failed.is_empty()
+            && self.depth <= max_guesses
+    }
+}
+
+impl DecisionTree {
+    // 答えごとの道をまとめる
+    pub fn from_paths(paths: &[Path], max_guesses: usize) -> Result<DecisionTree, String> {
+        let first = paths
+            .iter()
+            .find_map(|path| path.steps.first())
+            .ok_or("the tree is empty")?;
+        let mut root = Node::new(first.0);
+        for path in paths.iter().filter(|path| !path.steps.is_empty()) {
+            root.insert(&path.answer, &path.steps)?;
+        }
+        Ok(DecisionTree { root, max_guesses })
+    }
+    // 書き出したJSONか、公開されている形のテキスト
+    pub fn parse(variant: &dyn Variant, text: &str) -> Result<DecisionTree, String> {
+        if text.trim_start().starts_with('{') {
+            let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+            let max_guesses = value["max_guesses"].as_u64().unwrap_or(6) as usize;
+            let root = value.get("root").unwrap_or(&value);
+            let mut paths = vec![];
+            json_paths(variant, root, &mut vec![], &mut paths)?;
+            DecisionTree::from_paths(&paths, max_guesses)
+        } else {
+            DecisionTree::from_paths(&text_paths(variant, text)?, 6)
+        }
+    }
+    pub fn load(variant: &dyn Variant, path: &FilePath) -> Result<DecisionTree, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        DecisionTree::parse(variant, &text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+    // 根からstatusをたどったnode
+    pub fn node(&self, ids: &[PatternId]) -> Option<&Node> {
+        ids.iter().try_fold(&self.root, |node, id| {
+            node.children
+                .binary_search_by_key(id, |(id, _)| *id)
+                .ok()
+                .map(|index| &node.children[index].1)
+        })
+    }
+    pub fn verify(&self, answers: &[Word], inputs: &[Word]) -> Verification {
+        let paths = self.paths();
+        let known: HashSet<&Word> = answers.iter().collect();
+        let inputs: HashSet<&Word> = inputs.iter().collect();
+        let mut reached: HashMap<Word, usize> = HashMap::new();
+        paths
+            .iter()
+            .for_each(|path| *reached.entry(path.answer).or_insert(0) += 1);
+        // 答えの順にそろえる
+        let sorted = |mut words: Vec<Word>| {
+            words.sort_by_key(|word| word.0);
+            words.dedup();
+            words
+        };
+        let not_in_word_list = sorted(
+            paths
+                .iter()
+                .flat_map(|path| path.steps.iter().map(|(guess, _)| *guess))
+                .filter(|guess| !inputs.contains(guess))
+                .collect(),
+        );
+        Verification {
+            missing: answers
+                .iter()
+                .filter(|answer| !reached.contains_key(answer))
+                .cloned()
+                .collect(),
+            unknown: sorted(
+                reached
+                    .keys()
+                    .filter(|answer| !known.contains(answer))
+                    .cloned()
+                    .collect(),
+            ),
+            duplicates: sorted(
+                reached
+                    .iter()
+                    .filter(|(_, count)| **count > 1)
+                    .map(|(answer, _)| *answer)
+                    .collect(),
+            ),
+            inconsistent: paths
+                .iter()
+                .filter_map(|path| {
+                    path.steps
+                        .iter()
+                        .position(|(guess, id)| {
+                            Word::to_status(guess, &path.answer) != Status::from_id(*id)
+                        })
+                        .map(|turn| (path.answer, turn + 1))
+                })
+                .collect(),
+            not_in_word_list,
+            failed: self.failures(),
+            depth: self.depth(),
+            average: self.average(),
+        }
+    }
+    // firstがあれば一手目はそれにする。同じ候補の集合の一手は一度だけ計算する
+    pub fn build<S: Solver>(
+        answers: &[Word],
+        inputs: &[Word],
+        first: Option<&Word>,
+        max_guesses: usize,
+    ) -> Result<DecisionTree, String> {
+        let cache = RecommendationCache::new().shared();
+        let mut root: Option<Node> = None;
+        quietly(|| {
+            for answer in answers {
+                let mut board =
+                    Cached::<S>::new(answers.to_vec(), inputs.to_vec()).with_cache(cache.clone());
+                let mut path = vec![];
+                for turn in 1..=max_guesses {
+                    let guess = match (turn, first) {
+                        (1, Some(first)) => *first,
+                        _ => board.next_within(max_guesses + 1 - turn),
+                    };
+                    let id = pattern_id(&guess, answer);
+                    path.push((guess, id));
+                    if id == ALL_GREEN {
+                        break;
+                    }
+                    board.filter(&guess, &Status::from_id(id));
+                }
+                root.get_or_insert_with(|| Node::new(path[0].0))
+                    .insert(answer, &path)?;
+            }
+            Ok::<(), String>(())
+        })?;
+        let root = root.ok_or("no answers")?;
+        Ok(DecisionTree { root, max_guesses })
+    }
+    // 答えごとの道。statusの順
+    pub fn paths(&self) -> Vec<Path> {
+        let mut paths = vec![];
+        self.root.paths(&mut vec![], &mut paths);
+        paths
+    }
+    // 当てた答えの手数の平均
+    pub fn average(&self) -> f64 {
+        let solved: Vec<usize> = self
+            .paths()
+            .iter()
+            .filter(|path| path.solved())
+            .map(|path| path.steps.len())
+            .collect();
+        solved.iter().sum::<usize>() as f64 / solved.len().max(1) as f64
+    }
+    pub fn depth(&self) -> usize {
+        self.paths()
+            .iter()
+            .map(|path| path.steps.len())
+            .max()
+            .unwrap_or(0)
+    }
+    pub fn failures(&self) -> Vec<Word> {
+        self.paths()
+            .into_iter()
+            .filter(|path| !path.solved())
+            .map(|path| path.answer)
+            .collect()
+    }
+    pub fn export(&self, variant: &dyn Variant, format: Format) -> String {
+        match format {
+            Format::Json => self.to_json(variant).to_string(),
+            Format::Text => self.to_text(variant),
+            Format::Dot => self.to_dot(variant),
+        }
+    }
+    pub fn to_json(&self, variant: &dyn Variant) -> Value {
+        json!({
+            "max_guesses": self.max_guesses,
+            "answers": self.root.answers,
+            "average": self.average(),
+            "failed": self.failures().iter().map(|word| variant.display_word(word)).collect::<Vec<_>>(),
+            "root": self.root.to_json(variant),
+        })
+    }
+    // 「salet BBBBB1 courd BBYBB2 ...」を一行に一つ。前の行と同じところは空白にする
+    pub fn to_text(&self, variant: &dyn Variant) -> String {
+        let mut lines: Vec<String> = vec![];
+        let mut previous: Vec<(Word, PatternId)> = vec![];
+        for path in self.paths() {
+            let shared = path
+                .steps
+                .iter()
+                .zip(previous.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let mut line = String::new();
+            let mut blank = 0;
+            for (turn, (guess, id)) in path.steps.iter().enumerate() {
+                let word = variant.display_word(guess);
+                if turn == shared && shared < previous.len() {
+                    // 同じnodeでstatusだけが違う
+                    blank = line.chars().count() + word.chars().count() + 1;
+                }
+                line += &format!("{} {}{} ", word, published(*id), turn + 1);
+                if turn < shared {
+                    blank = line.chars().count();
+                }
+            }
+            if !path.solved() {
+                line += &format!("failed {}", variant.display_word(&path.answer));
+            }
+            let line: String = line
+                .chars()
+                .enumerate()
+                .map(|(i, c)| if i < blank { ' ' } else { c })
+                .collect();
+            lines.push(line.trim_end().to_string());
+            previous = path.steps;
+        }
+        lines.join("\n") + "\n"
+    }
+    pub fn to_dot(&self, variant: &dyn Variant) -> String {
+        fn walk(node: &Node, variant: &dyn Variant, next: &mut usize, lines: &mut Vec<String>) {
+            let name = *next;
+            *next += 1;
+            let peripheries = if node.solved { 2 } else { 1 };
+            lines.push(format!(
+                "  n{} [label=\"{}\\n{}\" peripheries={}];",
+                name,
+                variant.display_word(&node.guess),
+                node.answers,
+                peripheries
+            ));
+            for (id, child) in &node.children {
+                let to = *next;
+                walk(child, variant, next, lines);
+                lines.push(format!(
+                    "  n{} -> n{} [label=\"{}\"];",
+                    name,
+                    to,
+                    published(*id)
+                ));
+            }
+            for answer in &node.failed {
+                let to = *next;
+                *next += 1;
+                lines.push(format!(
+                    "  n{} [label=\"{}\" shape=plaintext fontcolor=red];",
+                    to,
+                    variant.display_word(answer)
+                ));
+                let id = pattern_id(&node.guess, answer);
+                lines.push(format!(
+                    "  n{} -> n{} [label=\"{}\"];",
+                    name,
+                    to,
+                    published(id)
+                ));
+            }
+        }
+        let mut lines = vec![
+            "digraph wordle {".to_string(),
+            "  node [shape=box];".to_string(),
+        ];
+        walk(&self.root, variant, &mut 0, &mut lines);
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+}
+
+fn json_paths(
+    variant: &dyn Variant,
+    node: &Value,
+    prefix: &mut Vec<(Word, PatternId)>,
+    paths: &mut Vec<Path>,
+) -> Result<(), String> {
+    let word = |value: &Value| -> Result<Word, String> {
+        variant.parse_word(value.as_str().ok_or(format!("not a word: {}", value))?)
+    };
+    let guess = word(&node["guess"])?;
+    if node["solved"].as_bool().unwrap_or(false) {
+        let mut steps = prefix.clone();
+        steps.push((guess, ALL_GREEN));
+        paths.push(Path {
+            answer: guess,
+            steps,
+        });
+    }
+    for answer in node["failed"].as_array().into_iter().flatten() {
+        let answer = word(answer)?;
+        let mut steps = prefix.clone();
+        steps.push((guess, pattern_id(&guess, &answer)));
+        paths.push(Path { answer, steps });
+    }
+    for child in node["children"].as_array().into_iter().flatten() {
+        let status = child["status"].as_str().ok_or("a child without status")?;
+        prefix.push((guess, status.parse::<Status>()?.to_id()));
+        json_paths(variant, child, prefix, paths)?;
+        prefix.pop();
+    }
+    Ok(())
+}
+
+// 字下げは前の行の単語かstatusの位置に合わせてあり、そこまでは前の行と同じ
+fn text_paths(variant: &dyn Variant, text: &str) -> Result<Vec<Path>, String> {
+    let mut paths: Vec<Path> = vec![];
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let error = |message: String| format!("line {}: {}", number + 1, message);
+        let indent = line.chars().take_while(|c| *c == ' ').count();
+        let previous: &[(Word, PatternId)] = paths.last().map_or(&[], |path| &path.steps);
+        // 前の行の単語とstatusが始まる位置
+        let mut column = 0;
+        let mut inherited = None;
+        for (turn, (guess, _)) in previous.iter().enumerate() {
+            if column == indent {
+                inherited = Some((turn, None));
+            }
+            column += variant.display_word(guess).chars().count() + 1;
+            if column == indent {
+                inherited = Some((turn, Some(*guess)));
+            }
+            column += 5 + (turn + 1).to_string().len() + 1;
+        }
+        let (shared, mut guess) = match (indent, inherited) {
+            (0, _) => (0, None),
+            (_, Some(inherited)) => inherited,
+            _ => {
+                return Err(error(
+                    "the indentation does not match the previous line".into(),
+                ))
+            }
+        };
+        let mut steps = previous[..shared].to_vec();
+        let mut answer = None;
+        let mut tokens = line.split_whitespace();
+        while let Some(token) = tokens.next() {
+            let word = match guess.take() {
+                Some(word) => word,
+                None if token == "failed" => {
+                    let word = tokens
+                        .next()
+                        .ok_or(error("failed without an answer".into()))?;
+                    answer = Some(variant.parse_word(word).map_err(error)?);
+                    break;
+                }
+                None => {
+                    let word = variant.parse_word(token).map_err(error)?;
+                    if steps.last().is_some_and(|(_, id)| *id == ALL_GREEN) {
+                        return Err(error(format!("{} after GGGGG", token)));
+                    }
+                    guess = Some(word);
+                    continue;
+                }
+            };
+            // 全角の文字などが混ざっていても文字の境目で分ける
+            let split = token
+                .char_indices()
+                .nth(5)
+                .map_or(token.len(), |(index, _)| index);
+            let (status, turn) = token.split_at(split);
+            if turn != (steps.len() + 1).to_string() {
+                return Err(error(format!(
+                    "expected turn {}: {}",
+                    steps.len() + 1,
+                    token
+                )));
+            }
+            steps.push((word, parse_published(status).map_err(error)?));
+        }
+        if let Some(word) = guess {
+            return Err(error(format!("{} without a status", word)));
+        }
+        let answer = match (answer, steps.last()) {
+            (Some(answer), _) => answer,
+            (None, Some((guess, ALL_GREEN))) => *guess,
+            _ => return Err(error("the line does not end with GGGGG".into())),
+        };
+        paths.push(Path { answer, steps });
+    }
+    Ok(paths)
+}
+
+fn parse_published(s: &str) -> Result<PatternId, String> {
+    let chars: Vec<StatusChar> = s
+        .chars()
+        .map(|c| match c {
+            'B' => Ok(StatusChar::Gray),
+            'Y' => Ok(StatusChar::Yellow),
+            'G' => Ok(StatusChar::Green),
+            _ => Err(format!("not a status: {}", s)),
+        })
+        .collect::<Result<_, _>>()?;
+    let status: [StatusChar; 5] = chars
+        .try_into()
+        .map_err(|_| format!("not a 5 letter status: {}", s))?;
+    Ok(Status(status).to_id())
+}
+
+// 公開されている木で使われるB（灰色）、Y、Gの形
+fn published(id: PatternId) -> String {
+    Status::from_id(id)
+        .0
+        .iter()
+        .map(|c| match c {
+            StatusChar::Gray => 'B',
+            StatusChar::Yellow => 'Y',
+            StatusChar::Green => 'G',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tactics::position_freq, CANDITATES};
+    #[test]
+    fn tree() {
+        let answers = CANDITATES.get_canditates()[..100].to_vec();
+        let first: Word = "soare".parse().unwrap();
+        let tree = DecisionTree::build::<position_freq::Board>(
+            &answers,
+            &CANDITATES.get_all_words(),
+            Some(&first),
+            6,
+        )
+        .unwrap();
+        assert_eq!(tree.root.guess, first);
+        assert_eq!(tree.root.answers, 100);
+        let paths = tree.paths();
+        assert_eq!(paths.len(), 100);
+        // どの道もその答えでのstatusをたどる
+        paths.iter().for_each(|path| {
+            path.steps
+                .iter()
+                .for_each(|(guess, id)| assert_eq!(pattern_id(guess, &path.answer), *id));
+        });
+        assert!(tree.failures().is_empty());
+        assert!(tree.average() > 2.0 && tree.average() < 5.0);
+
+        let variant: &dyn Variant = &*CANDITATES;
+        let text = tree.to_text(variant);
+        assert_eq!(text.lines().count(), 100);
+        assert!(text.starts_with("soare "));
+        assert!(text.lines().skip(1).all(|line| line.starts_with(' ')));
+        let json: Value = serde_json::from_str(&tree.export(variant, Format::Json)).unwrap();
+        assert_eq!(json["root"]["guess"], "soare");
+        assert_eq!(json["answers"], 100);
+        let dot = tree.to_dot(variant);
+        assert!(dot.starts_with("digraph"));
+        let nodes = dot
+            .lines()
+            .filter(|line| line.contains("[label=") && !line.contains(" -> "))
+            .count();
+        assert_eq!(dot.matches(" -> ").count(), nodes - 1);
+
+        // 書き出したものを読み込むと同じ木になる
+        assert_eq!(DecisionTree::parse(variant, &text).unwrap(), tree);
+        assert_eq!(
+            DecisionTree::parse(variant, &json.to_string()).unwrap(),
+            tree
+        );
+        let verification = tree.verify(&answers, &CANDITATES.get_all_words());
+        assert!(verification.is_ok(6));
+        assert_eq!(verification.depth, tree.depth());
+        let verification = tree.verify(&CANDITATES.get_canditates()[..101], &answers);
+        assert_eq!(verification.missing.len(), 1);
+        assert!(!verification.not_in_word_list.is_empty());
+        // 枝のstatusを書きかえると、その下の答えがおかしいとわかる
+        let mut broken = tree.clone();
+        broken.root.children[0].0 = ALL_GREEN - 1;
+        let verification = broken.verify(&answers, &CANDITATES.get_all_words());
+        assert_eq!(
+            verification.inconsistent.len(),
+            tree.root.children[0].1.answers
+        );
+        assert!(DecisionTree::parse(variant, &text.replacen("GGGGG", "GGGGY", 1)).is_err());
+        assert!(DecisionTree::parse(variant, " soare BBBBB1").is_err());
+        assert!(DecisionTree::parse(variant, "soare ＢＢＢＢＢ1").is_err());
+        assert!(DecisionTree::parse(variant, "soare BBBBＢ1").is_err());
+
+        // 答えのリストにない答えや、二つの葉にある答えも通さない
+        let verification = tree.verify(&answers[1..], &CANDITATES.get_all_words());
+        assert_eq!(verification.unknown, vec![answers[0]]);
+        assert!(!verification.is_ok(6));
+        let twice = "soare BBBBB1 cigar GGGGG2\n      BBBBY1 cigar GGGGG2";
+        let cigar: Word = "cigar".parse().unwrap();
+        let verification = DecisionTree::parse(variant, twice)
+            .unwrap()
+            .verify(&[cigar], &CANDITATES.get_all_words());
+        assert_eq!(verification.duplicates, vec![cigar]);
+        assert!(verification.missing.is_empty() && !verification.is_ok(6));
+    }
+}
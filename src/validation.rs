@@ -0,0 +1,150 @@
+use crate::enums::{Status, Word};
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedbackError {
+    // 辞書のどの単語が答えでもこのstatusにはならない
+    Unproducible {
+        word: Word,
+        status: Status,
+    },
+    // historyのrow番目（0始まり）までと合わせると矛盾する
+    Conflict {
+        row: usize,
+        conflicting: (Word, Status),
+        word: Word,
+        status: Status,
+    },
+    // 辞書の単語とは矛盾しないが、答えの候補が残らない
+    NoCandidates {
+        word: Word,
+        status: Status,
+    },
+}
+
+impl fmt::Display for FeedbackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FeedbackError::Unproducible { word, status } => write!(
+                f,
+                "status {} can never be produced by guessing {}",
+                status, word
+            ),
+            FeedbackError::Conflict {
+                row,
+                conflicting: (c_word, c_status),
+                word,
+                status,
+            } => write!(
+                f,
+                "{} {} contradicts row {} ({} {})",
+                word,
+                status,
+                row + 1,
+                c_word,
+                c_status
+            ),
+            FeedbackError::NoCandidates { word, status } => {
+                write!(f, "no canditates remain after {} {}", word, status)
+            }
+        }
+    }
+}
+
+impl Error for FeedbackError {}
+
+pub fn check_status(
+    word: &Word,
+    status: &Status,
+    dictionary: &[Word],
+) -> Result<(), FeedbackError> {
+    if dictionary
+        .iter()
+        .any(|answer| Word::to_status(word, answer) == *status)
+    {
+        Ok(())
+    } else {
+        Err(FeedbackError::Unproducible {
+            word: *word,
+            status: *status,
+        })
+    }
+}
+
+// filterする前に、候補がなくならないかを調べる
+pub fn check_filter(
+    history: &[(Word, Status)],
+    remaining: &[Word],
+    dictionary: &[Word],
+    word: &Word,
+    status: &Status,
+) -> Result<(), FeedbackError> {
+    check_status(word, status, dictionary)?;
+    if remaining
+        .iter()
+        .any(|answer| Word::to_status(word, answer) == *status)
+    {
+        return Ok(());
+    }
+    // 辞書の単語で矛盾が起きる最初のrowを探す
+    let mut consistent: Vec<Word> = dictionary
+        .iter()
+        .filter(|answer| Word::to_status(word, answer) == *status)
+        .cloned()
+        .collect();
+    for (row, (h_word, h_status)) in history.iter().enumerate() {
+        consistent.retain(|answer| Word::to_status(h_word, answer) == *h_status);
+        if consistent.is_empty() {
+            return Err(FeedbackError::Conflict {
+                row,
+                conflicting: (*h_word, *h_status),
+                word: *word,
+                status: *status,
+            });
+        }
+    }
+    Err(FeedbackError::NoCandidates {
+        word: *word,
+        status: *status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CANDITATES;
+    fn row(word: &str, status: &str) -> (Word, Status) {
+        (word.parse().unwrap(), status.parse().unwrap())
+    }
+    #[test]
+    fn unproducible() {
+        let dictionary = CANDITATES.get_all_words();
+        let (word, status) = row("soare", "ggggy");
+        assert_eq!(
+            check_status(&word, &status, &dictionary),
+            Err(FeedbackError::Unproducible { word, status })
+        );
+        // 同じ文字が二つあって、後ろだけyellowにはならない
+        let (word, status) = row("speed", "___y_");
+        assert!(check_status(&word, &status, &dictionary).is_err());
+    }
+    #[test]
+    fn conflict() {
+        let dictionary = CANDITATES.get_all_words();
+        let history = vec![row("soare", "_y__g"), row("cloth", "__g__")];
+        let remaining: Vec<Word> = CANDITATES
+            .get_canditates()
+            .into_iter()
+            .filter(|answer| {
+                history
+                    .iter()
+                    .all(|(w, s)| Word::to_status(w, answer) == *s)
+            })
+            .collect();
+        let (word, status) = row("pluck", "_g___");
+        let error = check_filter(&history, &remaining, &dictionary, &word, &status).unwrap_err();
+        assert!(matches!(error, FeedbackError::Conflict { row: 1, .. }));
+        let (word, status) = row("glove", "__g_g");
+        assert!(check_filter(&history, &remaining, &dictionary, &word, &status).is_ok());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::{enums::Word, priors::Priors, words::Canditates};
+use std::{fs, path::Path};
+
+// wordleの亜種（使う文字と単語リストと表示）をまとめたもの
+pub trait Variant: Send + Sync {
+    fn name(&self) -> &str;
+    fn letters(&self) -> Vec<char>;
+    fn get_canditates(&self) -> Vec<Word>;
+    fn get_all_words(&self) -> Vec<Word>;
+    fn normalize(&self, s: &str) -> String {
+        s.to_lowercase()
+    }
+    fn parse_word(&self, s: &str) -> Result<Word, String> {
+        let word: Word = self.normalize(s.trim()).parse()?;
+        let letters = self.letters();
+        match word.0.iter().find(|a| !letters.contains(&a.0)) {
+            Some(a) => Err(format!("{} is not in the {} alphabet", a, self.name())),
+            None => Ok(word),
+        }
+    }
+    fn display_word(&self, word: &Word) -> String {
+        word.to_string()
+    }
+    fn keyboard(&self) -> Vec<String> {
+        self.letters()
+            .chunks(10)
+            .map(|row| row.iter().collect())
+            .collect()
+    }
+    fn priors(&self) -> Option<Priors> {
+        None
+    }
+}
+
+macro_rules! delegate_variant {
+    ($t:ty) => {
+        impl<V: Variant + ?Sized> Variant for $t {
+            fn name(&self) -> &str {
+                (**self).name()
+            }
+            fn letters(&self) -> Vec<char> {
+                (**self).letters()
+            }
+            fn get_canditates(&self) -> Vec<Word> {
+                (**self).get_canditates()
+            }
+            fn get_all_words(&self) -> Vec<Word> {
+                (**self).get_all_words()
+            }
+            fn normalize(&self, s: &str) -> String {
+                (**self).normalize(s)
+            }
+            fn parse_word(&self, s: &str) -> Result<Word, String> {
+                (**self).parse_word(s)
+            }
+            fn display_word(&self, word: &Word) -> String {
+                (**self).display_word(word)
+            }
+            fn keyboard(&self) -> Vec<String> {
+                (**self).keyboard()
+            }
+            fn priors(&self) -> Option<Priors> {
+                (**self).priors()
+            }
+        }
+    };
+}
+
+delegate_variant!(&V);
+delegate_variant!(Box<V>);
+
+impl Variant for Canditates {
+    fn name(&self) -> &str {
+        "english"
+    }
+    fn letters(&self) -> Vec<char> {
+        ('a'..='z').collect()
+    }
+    fn get_canditates(&self) -> Vec<Word> {
+        Canditates::get_canditates(self)
+    }
+    fn get_all_words(&self) -> Vec<Word> {
+        Canditates::get_all_words(self)
+    }
+    fn keyboard(&self) -> Vec<String> {
+        ["qwertyuiop", "asdfghjkl", "zxcvbnm"]
+            .iter()
+            .map(|row| row.to_string())
+            .collect()
+    }
+}
+
+pub struct Language {
+    pub name: &'static str,
+    pub letters: &'static str,
+    // 空ならlettersを10文字ずつ並べる
+    pub keyboard: &'static [&'static str],
+    normalize: fn(&str) -> String,
+    display: fn(&Word) -> String,
+}
+
+pub static KANA: Language = Language {
+    name: "kana",
+    letters: "ぁあぃいぅうぇえぉおかがきぎくぐけげこごさざしじすずせぜそぞただちぢっつづてでとどなにぬねのはばぱひびぴふぶぷへべぺほぼぽまみむめもゃやゅゆょよらりるれろゎわゐゑをんゔゕゖー",
+    keyboard: &[],
+    normalize: to_hiragana,
+    display: |word| word.to_string(),
+};
+
+pub static GERMAN: Language = Language {
+    name: "german",
+    letters: "abcdefghijklmnopqrstuvwxyzäöüß",
+    keyboard: &["qwertzuiopü", "asdfghjklöä", "yxcvbnmß"],
+    normalize: |s| s.to_lowercase(),
+    display: to_tiles,
+};
+
+pub static SPANISH: Language = Language {
+    name: "spanish",
+    letters: "abcdefghijklmnopqrstuvwxyzñ",
+    keyboard: &["qwertyuiop", "asdfghjklñ", "zxcvbnm"],
+    normalize: strip_spanish_accents,
+    display: to_tiles,
+};
+
+// 自前の単語リストで英語を解くとき
+pub static ENGLISH: Language = Language {
+    name: "english",
+    letters: "abcdefghijklmnopqrstuvwxyz",
+    keyboard: &["qwertyuiop", "asdfghjkl", "zxcvbnm"],
+    normalize: |s| s.to_lowercase(),
+    display: |word| word.to_string(),
+};
+
+pub static LANGUAGES: [&Language; 4] = [&ENGLISH, &KANA, &GERMAN, &SPANISH];
+
+pub fn language(name: &str) -> Option<&'static Language> {
+    LANGUAGES.iter().find(|l| l.name == name).copied()
+}
+
+// カタカナで入力されてもひらがなとして扱う
+fn to_hiragana(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            c => c,
+        })
+        .collect()
+}
+
+// スペイン語版のwordleはñ以外のアクセントを区別しない
+fn strip_spanish_accents(s: &str) -> String {
+    s.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' => 'a',
+            'é' => 'e',
+            'í' => 'i',
+            'ó' => 'o',
+            'ú' | 'ü' => 'u',
+            c => c,
+        })
+        .collect()
+}
+
+// ßのように大文字が一文字にならないものはそのまま
+fn to_tiles(word: &Word) -> String {
+    word.0
+        .iter()
+        .map(|a| {
+            let mut upper = a.0.to_uppercase();
+            match (upper.next(), upper.next()) {
+                (Some(u), None) => u,
+                _ => a.0,
+            }
+        })
+        .collect()
+}
+
+// 単語リストをファイルから読み込んだ亜種
+pub struct LoadedVariant {
+    language: &'static Language,
+    canditates: Vec<Word>,
+    all_words: Vec<Word>,
+}
+
+impl LoadedVariant {
+    pub fn load(
+        language: &'static Language,
+        canditates_path: &Path,
+        all_words_path: &Path,
+    ) -> Result<LoadedVariant, String> {
+        let read = |path: &Path| {
+            fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+        };
+        LoadedVariant::from_lists(language, &read(canditates_path)?, &read(all_words_path)?)
+    }
+    // 一行一単語、#から始まる行と空行は無視する
+    pub fn from_lists(
+        language: &'static Language,
+        canditates: &str,
+        all_words: &str,
+    ) -> Result<LoadedVariant, String> {
+        let mut variant = LoadedVariant {
+            language,
+            canditates: vec![],
+            all_words: vec![],
+        };
+        variant.canditates = variant.parse_list(canditates)?;
+        // 答えの候補は常に入力できる
+        let mut words = variant.canditates.clone();
+        for word in variant.parse_list(all_words)? {
+            if !words.contains(&word) {
+                words.push(word);
+            }
+        }
+        variant.all_words = words;
+        Ok(variant)
+    }
+    fn parse_list(&self, list: &str) -> Result<Vec<Word>, String> {
+        list.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| self.parse_word(line))
+            .collect()
+    }
+}
+
+impl Variant for LoadedVariant {
+    fn name(&self) -> &str {
+        self.language.name
+    }
+    fn letters(&self) -> Vec<char> {
+        self.language.letters.chars().collect()
+    }
+    fn get_canditates(&self) -> Vec<Word> {
+        self.canditates.clone()
+    }
+    fn get_all_words(&self) -> Vec<Word> {
+        self.all_words.clone()
+    }
+    fn normalize(&self, s: &str) -> String {
+        (self.language.normalize)(s)
+    }
+    fn display_word(&self, word: &Word) -> String {
+        (self.language.display)(word)
+    }
+    fn keyboard(&self) -> Vec<String> {
+        if self.language.keyboard.is_empty() {
+            return self
+                .letters()
+                .chunks(10)
+                .map(|row| row.iter().collect())
+                .collect();
+        }
+        self.language
+            .keyboard
+            .iter()
+            .map(|row| row.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CANDITATES;
+    #[test]
+    fn english() {
+        let word = CANDITATES.parse_word("CIGAR").unwrap();
+        assert_eq!(CANDITATES.display_word(&word), "cigar");
+        assert!(CANDITATES.parse_word("größe").is_err());
+    }
+    #[test]
+    fn kana() {
+        let variant =
+            LoadedVariant::from_lists(&KANA, "さくらもち\n", "# guesses\nカタカナゴ\n").unwrap();
+        assert_eq!(variant.get_all_words().len(), 2);
+        let guess = variant.parse_word("もちもちき").unwrap();
+        let answer = variant.parse_word("サクラモチ").unwrap();
+        let status = Word::to_status(&guess, &answer);
+        assert_eq!(status.to_string(), "yy___");
+        assert_eq!(
+            variant.display_word(&variant.get_all_words()[1]),
+            "かたかなご"
+        );
+    }
+    #[test]
+    fn german_and_spanish() {
+        let german = LoadedVariant::from_lists(&GERMAN, "Größe\n", "").unwrap();
+        assert_eq!(german.display_word(&german.get_canditates()[0]), "GRÖßE");
+        let spanish = LoadedVariant::from_lists(&SPANISH, "ÁRBOL\nniños\n", "").unwrap();
+        assert_eq!(spanish.get_canditates()[0].to_string(), "arbol");
+        assert_eq!(spanish.display_word(&spanish.get_canditates()[1]), "NIÑOS");
+        assert!(spanish.parse_word("straß").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    enums::{Status, Word},
+    set_console,
+    tactics::{avg_info_max::expected_info, dynamic::DynBoard},
+    variants::{language, LoadedVariant, Variant},
+    Console, CANDITATES,
+};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct Suggestion {
+    pub word: String,
+    pub info: f64,
+}
+
+// JSから使うboard。単語リストを渡さなければ埋め込みの英語のリストを使う
+#[wasm_bindgen]
+pub struct WordleBoard {
+    board: DynBoard,
+    variant: Box<dyn Variant>,
+}
+
+impl WordleBoard {
+    pub fn create(tactic: &str, variant: Box<dyn Variant>) -> Result<WordleBoard, String> {
+        Ok(WordleBoard {
+            board: DynBoard::new(tactic, variant.as_ref())?,
+            variant,
+        })
+    }
+    pub fn try_apply(&mut self, word: &str, status: &str) -> Result<usize, String> {
+        let word = self.variant.parse_word(word)?;
+        let status: Status = status.parse()?;
+        self.board.apply(&word, &status).map_err(|e| e.to_string())
+    }
+}
+
+#[wasm_bindgen]
+impl WordleBoard {
+    #[wasm_bindgen(constructor)]
+    pub fn new(tactic: &str) -> Result<WordleBoard, JsError> {
+        WordleBoard::create(tactic, Box::new(&*CANDITATES)).map_err(|e| JsError::new(&e))
+    }
+    // answersとguessesは一行一単語。languageはenglish、kana、german、spanish
+    #[wasm_bindgen(js_name = withWords)]
+    pub fn with_words(
+        tactic: &str,
+        language_name: &str,
+        answers: &str,
+        guesses: &str,
+    ) -> Result<WordleBoard, JsError> {
+        let language = language(language_name)
+            .ok_or_else(|| JsError::new(&format!("unknown language: {}", language_name)))?;
+        let variant =
+            LoadedVariant::from_lists(language, answers, guesses).map_err(|e| JsError::new(&e))?;
+        WordleBoard::create(tactic, Box::new(variant)).map_err(|e| JsError::new(&e))
+    }
+    // 残りの候補の数を返す
+    pub fn apply(&mut self, word: &str, status: &str) -> Result<usize, JsError> {
+        self.try_apply(word, status).map_err(|e| JsError::new(&e))
+    }
+    pub fn reset(&mut self) {
+        self.board.reset();
+    }
+    #[wasm_bindgen(js_name = next)]
+    pub fn next_word(&self) -> Option<String> {
+        self.board
+            .next()
+            .map(|word| self.variant.display_word(&word))
+    }
+    // 平均情報量の大きい順
+    pub fn suggestions(&self, limit: usize) -> Vec<Suggestion> {
+        let remaining = self.board.remaining();
+        if remaining.is_empty() {
+            return vec![];
+        }
+        let mut scores: Vec<(Word, f64)> = self
+            .board
+            .dictionary()
+            .iter()
+            .map(|word| (*word, expected_info(word, remaining, None)))
+            .collect();
+        scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        scores
+            .into_iter()
+            .take(limit)
+            .map(|(word, info)| Suggestion {
+                word: self.variant.display_word(&word),
+                info,
+            })
+            .collect()
+    }
+    pub fn canditates(&self, limit: usize) -> Vec<String> {
+        self.board
+            .remaining()
+            .iter()
+            .take(limit)
+            .map(|word| self.variant.display_word(word))
+            .collect()
+    }
+    pub fn remaining(&self) -> usize {
+        self.board.remaining().len()
+    }
+}
+
+// solverの途中経過を受け取るcallback。nullなら出さない
+#[wasm_bindgen(js_name = setConsole)]
+pub fn set_console_callback(callback: Option<js_sys::Function>) {
+    set_console(callback.map(|callback| {
+        Rc::new(move |line: &str| {
+            let _ = callback.call1(&JsValue::NULL, &JsValue::from_str(line));
+        }) as Console
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn board() {
+        let mut board = WordleBoard::new("position_freq").ok().unwrap();
+        assert_eq!(board.try_apply("soare", "__yy_"), Ok(42));
+        assert_eq!(board.canditates(1), vec!["cigar".to_string()]);
+        assert!(board.next_word().is_some());
+        assert!(board.try_apply("cigar", "ggggy").is_err());
+        assert_eq!(board.suggestions(3).len(), 3);
+        board.reset();
+        assert_eq!(board.remaining(), CANDITATES.get_canditates().len());
+        assert!(WordleBoard::create("nothing", Box::new(&*CANDITATES)).is_err());
+    }
+}